@@ -2,6 +2,11 @@
 
 use std::collections::VecDeque;
 use std::sync::Arc;
+use std::f32::INFINITY;
+
+use easing::EasingKind;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
 
 /// Just an `f32`.
 pub type Param = f32;
@@ -10,6 +15,9 @@ pub type Time = f32;
 /// Just an `f32`.
 pub type DeltaTime = f32;
 
+/// The time step used to estimate how fast an animation is changing.
+const VELOCITY_EPSILON: DeltaTime = 1e-3;
+
 /// An animation state.
 pub trait Animation<V>: Clone {
     /// Get the current output of the animation.
@@ -32,8 +40,9 @@ pub trait Animation<V>: Clone {
 pub enum Animate<V: Mixable> {
     /// Hold the given value.
     Fixed(V),
-    /// Move from `a→b` over the next `t` seconds, then stop.
-    Slide(V, V, Time),
+    /// Move from `a→b`, then stop, with `t` seconds left of the `d` the whole
+    /// move takes. `Animate::slide` starts one from the beginning.
+    Slide(V, V, Time, Time),
     /// Move from `a→b`, outputting `a` after `s` seconds then `b` after `t` seconds.
     Linear(V, V, Time, Time),
     /// Move through the curve `a→b→c`, outputting `a` after `s` seconds then `c` after `t` seconds.
@@ -52,6 +61,8 @@ pub enum Animate<V: Mixable> {
     SmoothSwitch(V, V, Time, Time),
     /// Soft switch from `a` to `b` using parameter `e` between `s` seconds and `t` seconds from now.
     SoftSwitch(V, V, i32, Time, Time),
+    /// Move from `a→b` along the given easing curve, starting after `s` seconds then ending after `t` seconds.
+    Eased(V, V, EasingKind, Time, Time),
     /// Outputs `f(x)` starting with `x=t`.
     Func(Arc<Fn(Time) -> V>, Time),
     /// `f(x)` mixes between `a` and `b` starting with `x=t`.
@@ -60,6 +71,8 @@ pub enum Animate<V: Mixable> {
     StepFunc(Arc<Fn(V, DeltaTime) -> V>, V),
     /// Play a sequence of animations.
     Sequence(AnimateSequence<V>),
+    /// Repeat an animation.
+    Loop(AnimateLoop<V>),
 }
 
 use self::Animate::*;
@@ -84,52 +97,277 @@ impl<V: Mixable> Animate<V> {
         }), a, b, 0.)
     }
 
-    #[inline]
-    fn do_step(self, dt: DeltaTime) -> Self {
+    /// Move from `a→b` over `duration` seconds, then stop.
+    pub fn slide(a: V, b: V, duration: Time) -> Animate<V> {
+        Slide(a, b, duration, duration)
+    }
+
+    /// Repeat `anim` forever, restarting it every `period` seconds.
+    pub fn looping(anim: Animate<V>, period: Time) -> Animate<V> {
+        Loop(AnimateLoop::new(anim, period, None))
+    }
+
+    /// Play `anim` `count` times, restarting it every `period` seconds, then
+    /// hold the value it reaches at the end of the last cycle.
+    pub fn repeat(anim: Animate<V>, period: Time, count: u32) -> Animate<V> {
+        Loop(AnimateLoop::new(anim, period, Some(count)))
+    }
+
+    /// Hold the current output for `time` seconds before playing this
+    /// animation.
+    pub fn delay(self, time: Time) -> Animate<V> {
+        let hold = Fixed(self.now());
+        match self {
+            Sequence(mut seq) => {
+                seq.before(time, hold);
+                Sequence(seq)
+            },
+            anim => {
+                let end = anim.end_value().unwrap_or_else(|| anim.now());
+                let mut seq = AnimateSequence::new(end);
+                seq.after(time, hold);
+                seq.after(INFINITY, anim);
+                Sequence(seq)
+            },
+        }
+    }
+
+    /// Play this animation for `time` seconds, then play `next` forever.
+    ///
+    /// Whatever this animation plays last is what gets cut to `time`
+    /// seconds: the animation itself, or the last entry queued in a
+    /// `Sequence`, so chained calls read like a timeline. An empty sequence
+    /// holds its `end` value for `time` seconds instead.
+    pub fn then(self, time: Time, next: Animate<V>) -> Animate<V> {
+        let mut seq = match self {
+            Sequence(seq) => seq,
+            anim => {
+                let mut seq = AnimateSequence::new(anim.now());
+                seq.after(INFINITY, anim);
+                seq
+            },
+        };
+        // The new timeline starts now, so its progress does too
+        seq.elapsed = 0.;
+        match seq.queue.back_mut() {
+            Some(last) => last.1 = time,
+            None => {
+                let hold = Fixed(seq.end.clone());
+                seq.after(time, hold);
+            },
+        }
+        seq.end = next.end_value().unwrap_or_else(|| next.now());
+        seq.after(INFINITY, next);
+        Sequence(seq)
+    }
+
+    /// Get the value this animation settles on once it finishes, or `None`
+    /// if it never settles (or it can't be known in advance).
+    pub fn end_value(&self) -> Option<V> {
+        match *self {
+            Fixed(ref a) => Some(a.clone()),
+            Slide(_, ref b, _, _) => Some(b.clone()),
+            Linear(..) | Quadratic(..) | Cubic(..) => None,
+            BoundedLinear(_, ref b, _, _) => Some(b.clone()),
+            BoundedQuadratic(_, _, ref c, _, _) => Some(c.clone()),
+            BoundedCubic(_, _, _, ref d, _, _) => Some(d.clone()),
+            Switch(_, ref b, _) => Some(b.clone()),
+            SmoothSwitch(_, ref b, _, _) => Some(b.clone()),
+            SoftSwitch(_, ref b, _, _, _) => Some(b.clone()),
+            Eased(_, ref b, _, _, _) => Some(b.clone()),
+            Func(..) | MixFunc(..) | StepFunc(..) => None,
+            Sequence(ref seq) => if seq.open_ended() {
+                seq.queue.back().and_then(|&(ref a, _)| a.end_value())
+            } else {
+                Some(seq.end.clone())
+            },
+            Loop(ref lp) => if lp.finished() {
+                Some(lp.now())
+            } else if lp.remaining.is_some() {
+                let mut last = (*lp.pristine).clone();
+                last.step(lp.period);
+                Some(last.now())
+            } else {
+                None
+            },
+        }
+    }
+
+    /// Get how far through this animation is, from `0` to `1`, or `None` if
+    /// it has no fixed length. `Fixed` is always complete, and a `Switch` is
+    /// not complete until it switches.
+    ///
+    /// `Sequence` counts the time spent in entries it has already finished,
+    /// so its progress never goes backwards as it moves from one entry to
+    /// the next.
+    pub fn progress(&self) -> Option<Param> {
+        match *self {
+            Fixed(_) => Some(1.),
+            Switch(..) => Some(0.),
+            BoundedLinear(_, _, s, t) |
+            BoundedQuadratic(_, _, _, s, t) |
+            BoundedCubic(_, _, _, _, s, t) |
+            SmoothSwitch(_, _, s, t) |
+            SoftSwitch(_, _, _, s, t) |
+            Eased(_, _, _, s, t) => Some(if s > 0. {
+                0.
+            } else if t <= 0. {
+                1.
+            } else {
+                (s / (s - t)).min(1.)
+            }),
+            Slide(_, _, t, d) => Some(if d > 0. { 1. - t / d } else { 1. }),
+            Linear(..) | Quadratic(..) | Cubic(..) => None,
+            Func(..) | MixFunc(..) | StepFunc(..) => None,
+            Sequence(ref seq) => seq.remaining_time().map(|left| {
+                let total = seq.elapsed + left;
+                if total > 0. && total.is_finite() { seq.elapsed / total } else { 1. }
+            }),
+            Loop(ref lp) => if lp.finished() { Some(1.) } else { None },
+        }
+    }
+
+    /// Get how many seconds are left until this animation settles, or
+    /// `None` if it never does (or it can't be known in advance).
+    pub fn remaining_time(&self) -> Option<Time> {
+        match *self {
+            Fixed(_) => Some(0.),
+            Slide(_, _, t, _) | Switch(_, _, t) => Some(t.max(0.)),
+            BoundedLinear(_, _, _, t) |
+            BoundedQuadratic(_, _, _, _, t) |
+            BoundedCubic(_, _, _, _, _, t) |
+            SmoothSwitch(_, _, _, t) |
+            SoftSwitch(_, _, _, _, t) |
+            Eased(_, _, _, _, t) => Some(t.max(0.)),
+            Linear(..) | Quadratic(..) | Cubic(..) => None,
+            Func(..) | MixFunc(..) | StepFunc(..) => None,
+            Sequence(ref seq) => seq.remaining_time(),
+            Loop(ref lp) => match lp.remaining {
+                Some(0) => Some(0.),
+                Some(n) => Some((n - 1) as Time * lp.period + (lp.period - lp.phase).max(0.)),
+                None => None,
+            },
+        }
+    }
+
+    /// Estimate how fast the output is changing, per second, by looking
+    /// `eps` seconds ahead. The result is a difference of values, so it is
+    /// only meaningful for vector-like types.
+    pub fn velocity(&self, eps: DeltaTime) -> V {
+        let mut ahead = self.clone();
+        ahead.step(eps);
+        V::mix(vec![(ahead.now(), 1. / eps), (self.now(), -1. / eps)].into_iter())
+    }
+
+    /// Head for `target` instead, arriving in `remaining` seconds. The
+    /// output carries on from where it is now, at the speed it is moving
+    /// now, and curves towards the new target, so there is no jump in
+    /// either.
+    pub fn retarget(&mut self, target: V, remaining: Time) {
+        let from = self.now();
+        *self = if remaining <= 0. {
+            Fixed(target)
+        } else if self.steady() {
+            Animate::slide(from, target, remaining)
+        } else {
+            // A quadratic curve starts out heading for its middle point, at
+            // twice the speed needed to reach it in the whole duration, so
+            // put the middle point where the current motion would reach in
+            // half the time
+            let mut ahead = self.clone();
+            ahead.step(VELOCITY_EPSILON);
+            let mid = V::linear(&from, &ahead.now(), remaining / (2. * VELOCITY_EPSILON));
+            BoundedQuadratic(from, mid, target, 0., remaining)
+        };
+    }
+
+    /// Get the time-reversed animation, which plays back everything this
+    /// animation has output so far in reverse, ending at the value it
+    /// started from. The reversed animation begins at the current output, so
+    /// there is never a jump.
+    ///
+    /// `StepFunc` does not remember its past, so it holds its current value,
+    /// as do animations which have not started yet.
+    ///
+    /// `Sequence` is the exception. Finished entries have already been
+    /// dropped from the queue, so instead the entries still queued are
+    /// played backwards, last to first, each for the time it was given, and
+    /// the reversed sequence ends at the current output. An entry that
+    /// settles before its time is up holds its end value first. An entry that plays
+    /// forever is given the time it takes to settle, or dropped if it never
+    /// does. Reversing twice gives back the original timeline.
+    pub fn reverse(self) -> Animate<V> where V: 'static {
         match self {
             Fixed(a) => Fixed(a),
-            Slide(a, b, t) => if dt > t {
-                Fixed(b)
+            Slide(a, b, t, d) => if t < d {
+                let now = V::linear(&a, &b, 1. - t / d);
+                Slide(now, a, d - t, d - t)
             } else {
-                Slide(V::linear(&a, &b, dt / t), b, t - dt)
+                Fixed(a)
             },
-            Linear(a, b, s, t) => Linear(a, b, s - dt, t - dt),
-            Quadratic(a, b, c, s, t) => Quadratic(a, b, c, s - dt, t - dt),
-            Cubic(a, b, c, d, s, t) => Cubic(a, b, c, d, s - dt, t- dt),
-            BoundedLinear(a, b, s, t) => if dt > t {
-                Fixed(b)
+            Linear(a, b, s, t) => Linear(b, a, -t, -s),
+            Quadratic(a, b, c, s, t) => Quadratic(c, b, a, -t, -s),
+            Cubic(a, b, c, d, s, t) => Cubic(d, c, b, a, -t, -s),
+            BoundedLinear(a, b, s, t) => if s > 0. {
+                Fixed(a)
             } else {
-                BoundedLinear(a, b, s - dt, t - dt)
+                BoundedLinear(b, a, -t, -s)
             },
-            BoundedQuadratic(a, b, c, s, t) => if dt > t {
-                Fixed(c)
+            BoundedQuadratic(a, b, c, s, t) => if s > 0. {
+                Fixed(a)
             } else {
-                BoundedQuadratic(a, b, c, s - dt, t - dt)
+                BoundedQuadratic(c, b, a, -t, -s)
             },
-            BoundedCubic(a, b, c, d, s, t) => if dt > t {
-                Fixed(d)
+            BoundedCubic(a, b, c, d, s, t) => if s > 0. {
+                Fixed(a)
             } else {
-                BoundedCubic(a, b, c, d, s - dt, t - dt)
+                BoundedCubic(d, c, b, a, -t, -s)
             },
-            Switch(a, b, t) => if dt > t {
-                Fixed(b)
+            // Has not switched yet, so it has only ever output `a`
+            Switch(a, _, _) => Fixed(a),
+            SmoothSwitch(a, b, s, t) => if s > 0. {
+                Fixed(a)
             } else {
-                Switch(a, b, t - dt)
+                // Smoothstep is symmetric, so swapping the ends is enough
+                SmoothSwitch(b, a, -t, -s)
             },
-            SmoothSwitch(a, b, s, t) => if dt > t {
-                Fixed(b)
+            SoftSwitch(a, b, e, s, t) => if s > 0. {
+                Fixed(a)
             } else {
-                SmoothSwitch(a, b, s - dt, t - dt)
+                // The power curve is not symmetric, so evaluate the original
+                // at ever earlier times until its start is reached
+                let mut seq = AnimateSequence::new(a.clone());
+                seq.before(-s, Func(Arc::new(move |x| {
+                    SoftSwitch(a.clone(), b.clone(), e, s + x, t + x).now()
+                }), 0.));
+                Sequence(seq)
             },
-            SoftSwitch(a, b, e, s, t) => if dt > t {
-                Fixed(b)
+            Eased(a, b, k, s, t) => if s > 0. {
+                Fixed(a)
             } else {
-                SoftSwitch(a, b, e, s - dt, t - dt)
+                Eased(b, a, k.mirror(), -t, -s)
+            },
+            Func(f, s) => Func(Arc::new(move |x| f(s - x)), 0.),
+            MixFunc(f, a, b, s) => MixFunc(Arc::new(move |x| f(s - x)), a, b, 0.),
+            StepFunc(_, v) => Fixed(v),
+            Sequence(seq) => {
+                let mut rev = AnimateSequence::new(seq.now());
+                for (mut a, t) in seq.queue.into_iter().rev() {
+                    let t = if t.is_infinite() { a.remaining_time().unwrap_or(0.) } else { t };
+                    // Play out the animation first, so that all of it is
+                    // played back. If it settles before its slot is up, it
+                    // held its end for the rest, so hold that first.
+                    let run = a.remaining_time().map_or(t, |left| left.min(t));
+                    a.step(run);
+                    if t > run {
+                        rev.after(t - run, Fixed(a.now()));
+                    }
+                    rev.after(run, a.reverse());
+                }
+                Sequence(rev)
             },
-            Func(f, s) => Func(f, s + dt),
-            MixFunc(f, a, b, s) => MixFunc(f, a, b, s + dt),
-            StepFunc(f, v) => { let v = f(v, dt); StepFunc(f, v) },
-            Sequence(mut seq) => { seq.step(dt); Sequence(seq) },
+            // Only the current cycle has a past worth playing back
+            Loop(lp) => (*lp.current).reverse(),
         }
     }
 }
@@ -138,7 +376,7 @@ impl<V: Mixable> Animation<V> for Animate<V> {
     fn now(&self) -> V {
         match *self {
             Fixed(ref x) => x.clone(),
-            Slide(ref x, _, _) => x.clone(),
+            Slide(ref a, ref b, t, d) => V::linear(a, b, if d > 0. { 1. - t / d } else { 1. }),
             Linear(ref a, ref b, s, t) => V::linear(a, b, s / (s - t)),
             Quadratic(ref a, ref b, ref c, s, t) => V::quadratic(a, b, c, s / (s - t)),
             Cubic(ref a, ref b, ref c, ref d, s, t) => V::cubic(a, b, c, d, s / (s - t)),
@@ -172,24 +410,88 @@ impl<V: Mixable> Animation<V> for Animate<V> {
                 let x = 1. - x;
                 V::linear(a, b, x.powi(e)) 
             },
+            Eased(ref a, ref b, k, s, t) => if s > 0. {
+                a.clone()
+            } else {
+                let x = (s / (s - t)).min(1.);
+                V::linear(a, b, k.apply(x))
+            },
             Func(ref f, t) => f(t),
             MixFunc(ref f, ref a, ref b, t) => V::linear(a, b, f(t)),
             StepFunc(_, ref v) => v.clone(),
             Sequence(ref seq) => seq.now(),
+            Loop(ref lp) => lp.now(),
         }
     }
 
     fn step(&mut self, dt: DeltaTime) {
-        let ptr = self as *mut Self;
-        use std::ptr::*;
-        unsafe {
-            write(ptr, read(ptr).do_step(dt));
+        // Update in place, only replacing `self` once the new state has been
+        // built, so a panicking closure leaves the animation as it was
+        let next = match *self {
+            Fixed(_) => None,
+            Slide(_, ref b, ref mut t, _) => if dt > *t {
+                Some(Fixed(b.clone()))
+            } else {
+                *t -= dt;
+                None
+            },
+            // These never settle, so they have no output after forever and
+            // keep the last one instead
+            Linear(_, _, ref mut s, ref mut t) |
+            Quadratic(_, _, _, ref mut s, ref mut t) |
+            Cubic(_, _, _, _, ref mut s, ref mut t) => {
+                if dt.is_finite() {
+                    *s -= dt;
+                    *t -= dt;
+                }
+                None
+            },
+            BoundedLinear(_, ref b, ref mut s, ref mut t) |
+            BoundedQuadratic(_, _, ref b, ref mut s, ref mut t) |
+            BoundedCubic(_, _, _, ref b, ref mut s, ref mut t) |
+            SmoothSwitch(_, ref b, ref mut s, ref mut t) |
+            SoftSwitch(_, ref b, _, ref mut s, ref mut t) |
+            Eased(_, ref b, _, ref mut s, ref mut t) => if dt > *t {
+                Some(Fixed(b.clone()))
+            } else {
+                *s -= dt;
+                *t -= dt;
+                None
+            },
+            Switch(_, ref b, ref mut t) => if dt > *t {
+                Some(Fixed(b.clone()))
+            } else {
+                *t -= dt;
+                None
+            },
+            Func(_, ref mut s) | MixFunc(_, _, _, ref mut s) => {
+                if dt.is_finite() {
+                    *s += dt;
+                }
+                None
+            },
+            StepFunc(ref f, ref mut v) => {
+                *v = f(v.clone(), dt);
+                None
+            },
+            Sequence(ref mut seq) => {
+                seq.step(dt);
+                None
+            },
+            Loop(ref mut lp) => {
+                lp.step(dt);
+                if lp.finished() { Some(Fixed(lp.now())) } else { None }
+            },
+        };
+        if let Some(next) = next {
+            *self = next;
         }
     }
 
     fn steady(&self) -> bool {
         match self {
             &Fixed(_) => true,
+            &Sequence(ref seq) => seq.steady(),
             _ => false,
         }
     }
@@ -197,11 +499,16 @@ impl<V: Mixable> Animation<V> for Animate<V> {
 
 /// A sequence of different animations, each one lasting a given duration.
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
 pub struct AnimateSequence<V: Mixable> {
     /// A queue of animations to play, and their durations.
     pub queue: VecDeque<(Animate<V>, Time)>,
     /// The value to hold once the queue is empty.
     pub end: V,
+    /// The time spent playing entries, including ones already finished and
+    /// removed from the queue.
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub elapsed: Time,
 }
 
 impl<V: Mixable> AnimateSequence<V> {
@@ -209,12 +516,65 @@ impl<V: Mixable> AnimateSequence<V> {
         AnimateSequence {
             queue: VecDeque::new(),
             end: end,
+            elapsed: 0.,
         }
     }
 
+    /// Start building a sequence in reading order with `then`. Equivalent to
+    /// `new`.
+    pub fn start(end: V) -> AnimateSequence<V> {
+        AnimateSequence::new(end)
+    }
+
     pub fn before(&mut self, time: Time, anim: Animate<V>) {
         self.queue.push_front((anim, time));
     }
+
+    pub fn after(&mut self, time: Time, anim: Animate<V>) {
+        self.queue.push_back((anim, time));
+    }
+
+    /// Play `anim` for `time` seconds after everything already in the
+    /// sequence.
+    pub fn then(mut self, time: Time, anim: Animate<V>) -> AnimateSequence<V> {
+        self.after(time, anim);
+        self
+    }
+
+    /// Check if the last animation in the queue plays forever, so that `end`
+    /// is never reached.
+    pub fn open_ended(&self) -> bool {
+        match self.queue.back() {
+            Some(&(_, t)) => t.is_infinite(),
+            None => false,
+        }
+    }
+
+    /// Get how many seconds are left until the sequence settles. An entry
+    /// that plays forever counts for as long as its animation takes to
+    /// settle, or makes the sequence endless if it never does.
+    pub fn remaining_time(&self) -> Option<Time> {
+        let mut left = 0.;
+        for &(ref a, t) in &self.queue {
+            left += if t.is_infinite() { a.remaining_time()? } else { t };
+        }
+        Some(left)
+    }
+
+    /// Hold the value output at the end of the last animation in the sequence
+    /// (or `end` if the sequence is empty) for `time` seconds.
+    pub fn then_hold(self, time: Time) -> AnimateSequence<V> {
+        let held = match self.queue.back() {
+            Some(&(ref a, t)) if t.is_infinite() => a.end_value().unwrap_or_else(|| a.now()),
+            Some(&(ref a, t)) => {
+                let mut a = a.clone();
+                a.step(t);
+                a.now()
+            },
+            None => self.end.clone(),
+        };
+        self.then(time, Fixed(held))
+    }
 }
 
 impl<V: Mixable> Animation<V> for AnimateSequence<V> {
@@ -224,30 +584,125 @@ impl<V: Mixable> Animation<V> for AnimateSequence<V> {
 
     fn step(&mut self, mut dt: DeltaTime) {
         // Logic to step over multiple animations when the `dt` is large
-        let mut count = 0;
-        for &mut (ref mut a, ref mut t) in self.queue.iter_mut() {
-            if dt > *t {
+        loop {
+            let t = match self.queue.front() {
+                Some(&(_, t)) => t,
+                // Everything has finished, and `end` doesn't change, so any
+                // residual `dt` is spent holding it
+                None => return,
+            };
+            // An entry that plays forever is never left, however big `dt` is
+            if dt >= t && t.is_finite() {
                 // step into next animation, bringing residual `dt` forward
-                dt -= *t;
-                count += 1;
+                dt -= t;
+                self.elapsed += t;
+                self.queue.pop_front();
             } else {
                 // step forward in current animation
-                a.step(dt);
-                *t -= dt;
-                break;
+                if let Some(&mut (ref mut a, ref mut t)) = self.queue.front_mut() {
+                    a.step(dt);
+                    if t.is_finite() {
+                        *t -= dt;
+                    }
+                }
+                self.elapsed += dt;
+                return;
             }
         }
-        self.queue.drain(..count);
     }
 
-    fn steady(&self) -> bool { 
-        match self.queue.back() {
-            Some(a) => a.0.steady(),
-            None => true,
+    /// A sequence is steady once it has nothing left to play, or its last
+    /// entry is steady and playing forever.
+    fn steady(&self) -> bool {
+        match self.queue.len() {
+            0 => true,
+            1 => self.open_ended() && self.queue[0].0.steady(),
+            _ => false,
         }
     }
 }
 
+/// An animation played over and over, restarting every period.
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub struct AnimateLoop<V: Mixable> {
+    /// The animation as it is at the start of every cycle.
+    pub pristine: Box<Animate<V>>,
+    /// The animation in the current cycle.
+    pub current: Box<Animate<V>>,
+    /// The length of each cycle.
+    pub period: Time,
+    /// The time elapsed in the current cycle.
+    pub phase: Time,
+    /// The number of cycles left to play, including the current one, or
+    /// `None` to loop forever.
+    pub remaining: Option<u32>,
+}
+
+impl<V: Mixable> AnimateLoop<V> {
+    pub fn new(anim: Animate<V>, period: Time, count: Option<u32>) -> AnimateLoop<V> {
+        AnimateLoop {
+            current: Box::new(anim.clone()),
+            pristine: Box::new(anim),
+            period: period,
+            phase: 0.,
+            remaining: count,
+        }
+    }
+
+    /// Check if every cycle has been played.
+    pub fn finished(&self) -> bool {
+        self.remaining == Some(0)
+    }
+}
+
+impl<V: Mixable> Animation<V> for AnimateLoop<V> {
+    fn now(&self) -> V {
+        self.current.now()
+    }
+
+    fn step(&mut self, dt: DeltaTime) {
+        if self.finished() { return }
+        if self.period <= 0. {
+            // A loop with no length can't make progress
+            self.remaining = self.remaining.map(|_| 0);
+            return;
+        }
+        let phase = self.phase + dt;
+        if !phase.is_finite() && self.remaining.is_none() {
+            // An endless loop has no phase after forever, so stay where it is
+            return;
+        }
+        if phase < self.period {
+            self.current.step(dt);
+            self.phase = phase;
+            return;
+        }
+
+        // Work out the number of cycles passed instead of stepping through
+        // them, so huge steps are cheap and small steps don't accumulate drift
+        let cycles = (phase / self.period).floor();
+        if let Some(n) = self.remaining {
+            if cycles >= n as f32 {
+                let mut last = (*self.pristine).clone();
+                last.step(self.period);
+                *self.current = Fixed(last.now());
+                self.phase = self.period;
+                self.remaining = Some(0);
+                return;
+            }
+            self.remaining = Some(n - cycles as u32);
+        }
+        self.phase = phase - cycles * self.period;
+        *self.current = (*self.pristine).clone();
+        self.current.step(self.phase);
+    }
+
+    fn steady(&self) -> bool {
+        self.finished() || self.pristine.steady()
+    }
+}
+
 /// A type that can be animated.
 pub trait Mixable: Sized + Clone {
     /// Most animations are performed by mixing together various provided values, 
@@ -325,6 +780,143 @@ impl Mixer<Self> for f64 {
 }
 impl Mixable for f64 { type Mixer = Self; }
 
+macro_rules! array_mix {
+    ($($n:expr),*) => ($(
+        impl Mixer<Self> for [f32; $n] {
+            fn new() -> Self { [0.; $n] }
+            fn add(&mut self, v: &Self, weight: Param) {
+                for (a, b) in self.iter_mut().zip(v.iter()) {
+                    *a += b * weight;
+                }
+            }
+            fn close(self) -> Self { self }
+        }
+        impl Mixable for [f32; $n] { type Mixer = Self; }
+    )*)
+}
+
+array_mix!(3, 4);
+
+/// An RGBA color with sRGB encoded color channels and linear alpha, like a
+/// color picked in an image editor. Colors are mixed in linear space, which
+/// avoids the dark, muddy midpoints of mixing the encoded values directly.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ColorSrgb(pub [f32; 4]);
+
+impl ColorSrgb {
+    /// Convert a color with linear channels, such as `app::BACKGROUND`.
+    pub fn from_linear(c: [f32; 4]) -> ColorSrgb {
+        ColorSrgb([encode_srgb(c[0]), encode_srgb(c[1]), encode_srgb(c[2]), c[3]])
+    }
+
+    /// Get the color with linear channels, as the PBR lights expect.
+    pub fn to_linear(&self) -> [f32; 4] {
+        let c = self.0;
+        [decode_srgb(c[0]), decode_srgb(c[1]), decode_srgb(c[2]), c[3]]
+    }
+}
+
+fn decode_srgb(x: f32) -> f32 {
+    if x <= 0.04045 { x / 12.92 } else { ((x + 0.055) / 1.055).powf(2.4) }
+}
+
+fn encode_srgb(x: f32) -> f32 {
+    if x <= 0.0031308 { x * 12.92 } else { 1.055 * x.powf(1. / 2.4) - 0.055 }
+}
+
+impl Mixer<ColorSrgb> for [f32; 4] {
+    fn new() -> Self { [0.; 4] }
+    fn add(&mut self, v: &ColorSrgb, weight: Param) {
+        Mixer::add(self, &v.to_linear(), weight);
+    }
+    fn close(self) -> ColorSrgb { ColorSrgb::from_linear(self) }
+}
+impl Mixable for ColorSrgb { type Mixer = [f32; 4]; }
+
+/// An angle in radians that wraps around, kept in `(-π, π]`. Mixing takes
+/// the short way around, so animating from 350° to 10° turns through 0°
+/// rather than back through 180°.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct WrappedAngle(pub f32);
+
+impl WrappedAngle {
+    pub fn new(radians: f32) -> WrappedAngle {
+        WrappedAngle(wrap_angle(radians))
+    }
+
+    pub fn from_degrees(degrees: f32) -> WrappedAngle {
+        WrappedAngle::new(degrees.to_radians())
+    }
+
+    pub fn radians(self) -> f32 { self.0 }
+
+    pub fn degrees(self) -> f32 { self.0.to_degrees() }
+}
+
+/// Wrap an angle in radians into `(-π, π]`.
+fn wrap_angle<F: Real>(a: F) -> F {
+    let a = a % F::two_pi();
+    if a > F::pi() {
+        a - F::two_pi()
+    } else if a <= -F::pi() {
+        a + F::two_pi()
+    } else {
+        a
+    }
+}
+
+/// Mixes angles by adding each one as whichever of its representatives
+/// (`a + 2πk`) is nearest the weighted mean of the angles added so far, or
+/// nearest the first angle while the weights added so far sum to zero. So
+/// mixing two angles always takes the short way around.
+///
+/// When several angles are spread more than π apart there is no single
+/// short way, and the result depends on the order they are added in, each
+/// being pulled towards the ones before it, starting from the first.
+pub struct AngleMixer<F: Real> {
+    sum: F,
+    weight: F,
+    first: Option<F>,
+}
+
+impl<F: Real> AngleMixer<F> {
+    fn empty() -> AngleMixer<F> {
+        AngleMixer {
+            sum: F::zero(),
+            weight: F::zero(),
+            first: None,
+        }
+    }
+
+    fn add_angle(&mut self, a: F, weight: Param) {
+        let near = match self.first {
+            None => {
+                self.first = Some(a);
+                a
+            },
+            Some(first) => if self.weight.abs() > F::default_epsilon() {
+                self.sum / self.weight
+            } else {
+                first
+            },
+        };
+        let w = F::from_f32(weight).unwrap();
+        self.sum += (near + wrap_angle(a - near)) * w;
+        self.weight += w;
+    }
+
+    fn angle(&self) -> F {
+        wrap_angle(self.sum)
+    }
+}
+
+impl Mixer<WrappedAngle> for AngleMixer<f32> {
+    fn new() -> Self { AngleMixer::empty() }
+    fn add(&mut self, v: &WrappedAngle, weight: Param) { self.add_angle(v.0, weight) }
+    fn close(self) -> WrappedAngle { WrappedAngle(self.angle()) }
+}
+impl Mixable for WrappedAngle { type Mixer = AngleMixer<f32>; }
+
 macro_rules! impl_mix {
     (<$g:ident: $gb:path> $i:ty = $t:ty, || $n:expr, |$ep:ident| $e:expr, |$cp:ident| $c:expr) => {
         impl<$g: $gb> Mixer<$i> for $t {
@@ -382,18 +974,72 @@ impl_mix!(
     || na::zero(),
     |v| v,
     |c| c);
-impl_mix!(
-    <F: Real> UnitQuaternion<F> = Quaternion<F>,
-    || na::zero(),
-    |v| v.unwrap(),
-    |c| Unit::try_new(c, F::default_epsilon()).unwrap_or(Unit::new_unchecked(c)));
+/// Mixes rotations by summing their weighted quaternions. Each quaternion is
+/// first flipped onto the same hemisphere as the first one added, since `q`
+/// and `-q` are the same rotation but would otherwise cancel out.
+pub struct UnitQuaternionMixer<F: Real> {
+    sum: Quaternion<F>,
+    first: Option<Quaternion<F>>,
+}
+
+impl<F: Real> Mixer<UnitQuaternion<F>> for UnitQuaternionMixer<F> {
+    fn new() -> Self {
+        UnitQuaternionMixer {
+            sum: na::zero(),
+            first: None,
+        }
+    }
+
+    fn add(&mut self, v: &UnitQuaternion<F>, weight: Param) {
+        let mut q = v.unwrap();
+        match self.first {
+            Some(f) => if f.coords.dot(&q.coords) < F::zero() { q = -q },
+            None => self.first = Some(q),
+        }
+        self.sum += q * F::from_f32(weight).unwrap();
+    }
+
+    fn close(self) -> UnitQuaternion<F> {
+        let c = self.sum;
+        Unit::try_new(c, F::default_epsilon()).unwrap_or(Unit::new_unchecked(c))
+    }
+}
+
+impl<F: Real> Mixable for UnitQuaternion<F> {
+    type Mixer = UnitQuaternionMixer<F>;
+
+    /// Spherical linear interpolation, which turns at a constant speed and
+    /// always takes the shorter way around.
+    fn linear(a: &Self, b: &Self, t: Param) -> Self {
+        let t = F::from_f32(t).unwrap();
+        let p = a.unwrap();
+        let mut q = b.unwrap();
+        let mut cos = p.coords.dot(&q.coords);
+        if cos < F::zero() {
+            q = -q;
+            cos = -cos;
+        }
+
+        // Nearly the same rotation, where a normalized lerp is just as good
+        // and slerp would divide by almost zero
+        if cos > F::from_f32(0.9995).unwrap() {
+            return Unit::new_normalize(p * (F::one() - t) + q * t);
+        }
+
+        let angle = cos.acos();
+        let sin = angle.sin();
+        Unit::new_normalize(
+            p * (((F::one() - t) * angle).sin() / sin) +
+            q * ((t * angle).sin() / sin))
+    }
+}
 
 type Isometry3Mixer<F> = 
     (<UnitQuaternion<F> as Mixable>::Mixer, <Translation3<F> as Mixable>::Mixer);
 impl<F: Real> Mixer<Isometry3<F>> for Isometry3Mixer<F> {
     fn new() -> Self { 
         (
-            <Quaternion<F> as Mixer<UnitQuaternion<F>>>::new(), 
+            <UnitQuaternionMixer<F> as Mixer<UnitQuaternion<F>>>::new(),
             <Vector3<F> as Mixer<Translation3<F>>>::new())
     }
 
@@ -406,7 +1052,16 @@ impl<F: Real> Mixer<Isometry3<F>> for Isometry3Mixer<F> {
         Isometry3::from_parts(Mixer::close(self.1), Mixer::close(self.0))
     }
 }
-impl<F: Real> Mixable for Isometry3<F> { type Mixer = Isometry3Mixer<F>; }
+impl<F: Real> Mixable for Isometry3<F> {
+    type Mixer = Isometry3Mixer<F>;
+
+    /// Slerp the rotation and lerp the translation.
+    fn linear(a: &Self, b: &Self, t: Param) -> Self {
+        Isometry3::from_parts(
+            Translation3::linear(&a.translation, &b.translation, t),
+            UnitQuaternion::linear(&a.rotation, &b.rotation, t))
+    }
+}
 
 type Similarity3Mixer<F> = 
     (<Isometry3<F> as Mixable>::Mixer, <F as Mixable>::Mixer);
@@ -424,4 +1079,656 @@ impl<F: Real + Mixable> Mixer<Similarity3<F>> for Similarity3Mixer<F> {
         Similarity3::from_isometry(Mixer::close(self.0), Mixer::close(self.1))
     }
 }
-impl<F: Real + Mixable> Mixable for Similarity3<F> { type Mixer = Similarity3Mixer<F>; }
\ No newline at end of file
+impl<F: Real + Mixable> Mixable for Similarity3<F> {
+    type Mixer = Similarity3Mixer<F>;
+
+    /// Slerp the rotation and lerp the translation and scaling.
+    fn linear(a: &Self, b: &Self, t: Param) -> Self {
+        Similarity3::from_isometry(
+            Isometry3::linear(&a.isometry, &b.isometry, t),
+            F::linear(&a.scaling(), &b.scaling(), t))
+    }
+}
+
+/// Mixes rotation matrices through their quaternions, so that the result is
+/// always a proper rotation, however far apart the inputs are.
+pub struct Rotation3Mixer<F: Real>(UnitQuaternionMixer<F>);
+
+impl<F: Real> Mixer<Rotation3<F>> for Rotation3Mixer<F> {
+    fn new() -> Self {
+        Rotation3Mixer(<UnitQuaternionMixer<F> as Mixer<UnitQuaternion<F>>>::new())
+    }
+
+    fn add(&mut self, v: &Rotation3<F>, weight: Param) {
+        Mixer::add(&mut self.0, &UnitQuaternion::from_rotation_matrix(v), weight);
+    }
+
+    fn close(self) -> Rotation3<F> {
+        Mixer::<UnitQuaternion<F>>::close(self.0).to_rotation_matrix()
+    }
+}
+impl<F: Real> Mixable for Rotation3<F> {
+    type Mixer = Rotation3Mixer<F>;
+
+    /// Slerp between the matching quaternions. The ends are returned as is,
+    /// rather than round tripped through a quaternion.
+    fn linear(a: &Self, b: &Self, t: Param) -> Self {
+        if t <= 0. {
+            return a.clone();
+        }
+        if t >= 1. {
+            return b.clone();
+        }
+        UnitQuaternion::linear(
+            &UnitQuaternion::from_rotation_matrix(a),
+            &UnitQuaternion::from_rotation_matrix(b),
+            t).to_rotation_matrix()
+    }
+}
+
+/// Mixes the rotation and translation of an `IsometryMatrix3` separately,
+/// like `Isometry3Mixer` but with a rotation matrix.
+pub struct IsometryMatrix3Mixer<F: Real> {
+    rotation: Rotation3Mixer<F>,
+    translation: <Translation3<F> as Mixable>::Mixer,
+}
+
+impl<F: Real> Mixer<IsometryMatrix3<F>> for IsometryMatrix3Mixer<F> {
+    fn new() -> Self {
+        IsometryMatrix3Mixer {
+            rotation: <Rotation3Mixer<F> as Mixer<Rotation3<F>>>::new(),
+            translation: <Vector3<F> as Mixer<Translation3<F>>>::new(),
+        }
+    }
+
+    fn add(&mut self, v: &IsometryMatrix3<F>, weight: Param) {
+        Mixer::add(&mut self.rotation, &v.rotation, weight);
+        Mixer::add(&mut self.translation, &v.translation, weight);
+    }
+
+    fn close(self) -> IsometryMatrix3<F> {
+        IsometryMatrix3::from_parts(Mixer::close(self.translation), Mixer::close(self.rotation))
+    }
+}
+impl<F: Real> Mixable for IsometryMatrix3<F> {
+    type Mixer = IsometryMatrix3Mixer<F>;
+
+    /// Slerp the rotation and lerp the translation, like `Isometry3`.
+    fn linear(a: &Self, b: &Self, t: Param) -> Self {
+        IsometryMatrix3::from_parts(
+            Translation3::linear(&a.translation, &b.translation, t),
+            Rotation3::linear(&a.rotation, &b.rotation, t))
+    }
+}
+
+impl<F: Real> Mixer<UnitComplex<F>> for AngleMixer<F> {
+    fn new() -> Self { AngleMixer::empty() }
+    fn add(&mut self, v: &UnitComplex<F>, weight: Param) { self.add_angle(v.angle(), weight) }
+    fn close(self) -> UnitComplex<F> { UnitComplex::new(self.angle()) }
+}
+/// Mixed by angle, the short way around like `WrappedAngle`.
+impl<F: Real> Mixable for UnitComplex<F> { type Mixer = AngleMixer<F>; }
+
+/// Serialization for `Animate`, which can't be derived because of the
+/// closure variants.
+#[cfg(feature = "serde")]
+mod serialize {
+    use serde::{Serialize, Serializer, Deserialize, Deserializer};
+
+    use easing::EasingKind;
+    use super::{Animate, AnimateSequence, AnimateLoop, Animation, Mixable, Time};
+    use super::Animate::*;
+
+    /// `Animate` without `Func`, `MixFunc` and `StepFunc`.
+    #[derive(Serialize, Deserialize)]
+    enum AnimateState<V: Mixable> {
+        Fixed(V),
+        Slide(V, V, Time, Time),
+        Linear(V, V, Time, Time),
+        Quadratic(V, V, V, Time, Time),
+        Cubic(V, V, V, V, Time, Time),
+        BoundedLinear(V, V, Time, Time),
+        BoundedQuadratic(V, V, V, Time, Time),
+        BoundedCubic(V, V, V, V, Time, Time),
+        Switch(V, V, Time),
+        SmoothSwitch(V, V, Time, Time),
+        SoftSwitch(V, V, i32, Time, Time),
+        Eased(V, V, EasingKind, Time, Time),
+        Sequence(AnimateSequence<V>),
+        Loop(AnimateLoop<V>),
+    }
+
+    impl<V: Mixable> Animate<V> {
+        /// Check if serializing this animation keeps all of it. The closure
+        /// variants (`Func`, `MixFunc` and `StepFunc`) can't be serialized,
+        /// so they are saved as `Fixed` at their current output instead, and
+        /// stop moving once restored.
+        pub fn serializes_exactly(&self) -> bool {
+            match *self {
+                Func(..) | MixFunc(..) | StepFunc(..) => false,
+                Sequence(ref seq) => seq.queue.iter().all(|&(ref a, _)| a.serializes_exactly()),
+                Loop(ref lp) => lp.pristine.serializes_exactly() && lp.current.serializes_exactly(),
+                _ => true,
+            }
+        }
+
+        fn to_state(&self) -> AnimateState<V> {
+            use self::AnimateState as S;
+            match *self {
+                Fixed(ref a) => S::Fixed(a.clone()),
+                Slide(ref a, ref b, t, d) => S::Slide(a.clone(), b.clone(), t, d),
+                Linear(ref a, ref b, s, t) => S::Linear(a.clone(), b.clone(), s, t),
+                Quadratic(ref a, ref b, ref c, s, t) =>
+                    S::Quadratic(a.clone(), b.clone(), c.clone(), s, t),
+                Cubic(ref a, ref b, ref c, ref d, s, t) =>
+                    S::Cubic(a.clone(), b.clone(), c.clone(), d.clone(), s, t),
+                BoundedLinear(ref a, ref b, s, t) => S::BoundedLinear(a.clone(), b.clone(), s, t),
+                BoundedQuadratic(ref a, ref b, ref c, s, t) =>
+                    S::BoundedQuadratic(a.clone(), b.clone(), c.clone(), s, t),
+                BoundedCubic(ref a, ref b, ref c, ref d, s, t) =>
+                    S::BoundedCubic(a.clone(), b.clone(), c.clone(), d.clone(), s, t),
+                Switch(ref a, ref b, t) => S::Switch(a.clone(), b.clone(), t),
+                SmoothSwitch(ref a, ref b, s, t) => S::SmoothSwitch(a.clone(), b.clone(), s, t),
+                SoftSwitch(ref a, ref b, e, s, t) => S::SoftSwitch(a.clone(), b.clone(), e, s, t),
+                Eased(ref a, ref b, k, s, t) => S::Eased(a.clone(), b.clone(), k, s, t),
+                Func(..) | MixFunc(..) | StepFunc(..) => S::Fixed(self.now()),
+                Sequence(ref seq) => S::Sequence(seq.clone()),
+                Loop(ref lp) => S::Loop(lp.clone()),
+            }
+        }
+
+        fn from_state(state: AnimateState<V>) -> Animate<V> {
+            use self::AnimateState as S;
+            match state {
+                S::Fixed(a) => Fixed(a),
+                S::Slide(a, b, t, d) => Slide(a, b, t, d),
+                S::Linear(a, b, s, t) => Linear(a, b, s, t),
+                S::Quadratic(a, b, c, s, t) => Quadratic(a, b, c, s, t),
+                S::Cubic(a, b, c, d, s, t) => Cubic(a, b, c, d, s, t),
+                S::BoundedLinear(a, b, s, t) => BoundedLinear(a, b, s, t),
+                S::BoundedQuadratic(a, b, c, s, t) => BoundedQuadratic(a, b, c, s, t),
+                S::BoundedCubic(a, b, c, d, s, t) => BoundedCubic(a, b, c, d, s, t),
+                S::Switch(a, b, t) => Switch(a, b, t),
+                S::SmoothSwitch(a, b, s, t) => SmoothSwitch(a, b, s, t),
+                S::SoftSwitch(a, b, e, s, t) => SoftSwitch(a, b, e, s, t),
+                S::Eased(a, b, k, s, t) => Eased(a, b, k, s, t),
+                S::Sequence(seq) => Sequence(seq),
+                S::Loop(lp) => Loop(lp),
+            }
+        }
+    }
+
+    impl<V: Mixable + Serialize> Serialize for Animate<V> {
+        fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+            self.to_state().serialize(serializer)
+        }
+    }
+
+    impl<'de, V: Mixable + Deserialize<'de>> Deserialize<'de> for Animate<V> {
+        fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+            AnimateState::deserialize(deserializer).map(Animate::from_state)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use easing::EasingKind;
+
+    /// Get the output of `anim` after `t` seconds, stepped all at once.
+    fn at<A: Animation<f32>>(anim: &A, t: Time) -> f32 {
+        let mut anim = anim.clone();
+        anim.step(t);
+        anim.now()
+    }
+
+    fn close(a: f32, b: f32) -> bool {
+        (a - b).abs() < 1e-4
+    }
+
+    #[test]
+    fn reversed_slide_slides_back() {
+        let mut slide = Slide(0., 2., 1., 1.);
+        slide.step(0.25);
+        let rev = slide.reverse();
+        assert!(close(rev.now(), 0.5));
+        assert!(close(at(&rev, 0.125), 0.25));
+        assert_eq!(at(&rev, 0.3), 0.);
+        assert_eq!(rev.end_value(), Some(0.));
+    }
+
+    #[test]
+    fn reversed_sequence_plays_the_queue_backwards() {
+        let mut seq = Fixed(0.)
+            .then(0.5, Slide(0., 1., 1., 1.))
+            .then(1., Eased(1., 3., EasingKind::QuadIn, 0., 2.));
+        seq.step(0.75);
+        // A quarter of the slide and all of the easing are left
+        let left = 2.75;
+        let rev = seq.clone().reverse();
+        assert!(close(rev.now(), 3.));
+        for i in 0..28 {
+            let t = (i as f32 * 0.1).min(left);
+            assert!(close(at(&rev, t), at(&seq, left - t)), "at {}", t);
+        }
+        assert!(close(at(&rev, 10.), seq.now()));
+    }
+
+    #[test]
+    fn reversing_a_sequence_twice_round_trips() {
+        let mut seq = Fixed(0.)
+            .then(0.5, Slide(0., 1., 1., 1.))
+            .then(1., Eased(1., 3., EasingKind::CubicOut, 0., 2.))
+            .then(1., Fixed(3.));
+        seq.step(0.75);
+        let back = seq.clone().reverse().reverse();
+        for i in 0..40 {
+            let t = i as f32 * 0.1;
+            assert!(close(at(&back, t), at(&seq, t)), "at {}", t);
+        }
+    }
+
+    #[test]
+    fn reversed_sequence_keeps_motion_in_long_slots() {
+        // The slide settles a second before its slot is up
+        let seq = Slide(0., 1., 1., 1.).then(2., Fixed(5.));
+        let mut rev = seq.reverse();
+        let samples: Vec<f32> = (0..6).map(|_| {
+            let now = rev.now();
+            rev.step(0.5);
+            now
+        }).collect();
+        for (i, (&got, &want)) in samples.iter().zip([5., 1., 1., 0.5, 0., 0.].iter()).enumerate() {
+            assert!(close(got, want), "at {}: {:?}", i as f32 * 0.5, samples);
+        }
+    }
+
+    #[test]
+    fn sequence_built_forwards_matches_before() {
+        let forwards = AnimateSequence::start(4.)
+            .then(1., Slide(0., 1., 1., 1.))
+            .then(0.5, Fixed(2.))
+            .then(2., Eased(2., 4., EasingKind::QuadInOut, 0., 2.));
+        let mut backwards = AnimateSequence::new(4.);
+        backwards.before(2., Eased(2., 4., EasingKind::QuadInOut, 0., 2.));
+        backwards.before(0.5, Fixed(2.));
+        backwards.before(1., Slide(0., 1., 1., 1.));
+        for i in 0..50 {
+            let t = i as f32 * 0.1;
+            assert_eq!(at(&forwards, t), at(&backwards, t), "at {}", t);
+        }
+        // Big steps still skip over whole entries
+        assert_eq!(at(&forwards, 1.7), at(&backwards, 1.7));
+        assert_eq!(at(&forwards, 100.), 4.);
+    }
+
+    #[test]
+    fn then_hold_holds_the_last_output() {
+        let seq = AnimateSequence::start(0.)
+            .then(1., Slide(0., 1., 2., 2.))
+            .then_hold(1.)
+            .then(1., Fixed(3.));
+        assert!(close(at(&seq, 1.5), 0.5));
+        assert!(close(at(&seq, 2.5), 3.));
+        assert!(close(at(&seq, 3.5), 0.));
+    }
+
+    #[test]
+    fn chained_then_matches_a_built_sequence() {
+        let chained = Slide(0., 1., 2., 2.)
+            .then(1., Fixed(5.))
+            .then(0.5, Eased(5., 6., EasingKind::QuadOut, 0., 1.));
+        let built = Sequence(AnimateSequence::start(6.)
+            .then(1., Slide(0., 1., 2., 2.))
+            .then(0.5, Fixed(5.))
+            .then(INFINITY, Eased(5., 6., EasingKind::QuadOut, 0., 1.)));
+        for i in 0..40 {
+            let t = i as f32 * 0.1;
+            assert_eq!(at(&chained, t), at(&built, t), "at {}", t);
+        }
+    }
+
+    #[test]
+    fn then_cuts_a_closed_sequence_like_anything_else() {
+        let closed = Sequence(AnimateSequence::start(9.).then(2., Slide(0., 1., 2., 2.)));
+        let seq = closed.then(1., Fixed(3.));
+        // The slide is cut to a second, as `Slide(..).then(1., ..)` would be
+        let plain = Slide(0., 1., 2., 2.).then(1., Fixed(3.));
+        for i in 0..30 {
+            let t = i as f32 * 0.1;
+            assert_eq!(at(&seq, t), at(&plain, t), "at {}", t);
+        }
+        // An empty sequence holds its end first
+        let empty = Sequence(AnimateSequence::new(7.)).then(1., Fixed(3.));
+        assert_eq!(at(&empty, 0.5), 7.);
+        assert_eq!(at(&empty, 1.5), 3.);
+    }
+
+    #[test]
+    fn stepping_forever_gives_no_nan() {
+        let seq = Fixed(0f32)
+            .then(1., Linear(0., 1., 0., 1.))
+            .then(1., Animate::looping(Slide(0., 1., 1., 1.), 1.));
+        let mut stepped = seq.clone();
+        stepped.step(INFINITY);
+        assert!(stepped.now().is_finite());
+        stepped.step(1.);
+        assert!(stepped.now().is_finite());
+        assert!(stepped.progress().map_or(true, |p| p.is_finite()));
+
+        let mut lin = Fixed(0f32).then(1., Linear(0., 1., 0., 1.));
+        lin.step(INFINITY);
+        assert!(lin.now().is_finite());
+        let held = AnimateSequence::start(0f32)
+            .then(INFINITY, Linear(0., 1., 0., 1.))
+            .then_hold(1.);
+        assert!(held.now().is_finite());
+        assert!(at(&Sequence(held), 5.).is_finite());
+    }
+
+    #[test]
+    fn slide_progress_counts_from_its_start() {
+        let mut slide = Slide(0f32, 2., 2., 2.);
+        assert_eq!(slide.progress(), Some(0.));
+        slide.step(0.5);
+        assert_eq!(slide.progress(), Some(0.25));
+        slide.step(5.);
+        assert_eq!(slide.progress(), Some(1.));
+    }
+
+    #[test]
+    fn sequence_progress_never_goes_backwards() {
+        let mut seq = Fixed(0f32)
+            .then(0.5, Slide(0., 1., 1., 1.))
+            .then(1., Eased(1., 3., EasingKind::QuadIn, 0., 2.));
+        let mut last = seq.progress().unwrap();
+        assert_eq!(last, 0.);
+        for _ in 0..40 {
+            seq.step(0.1);
+            let p = seq.progress().unwrap();
+            assert!(p >= last, "{} after {}", p, last);
+            last = p;
+        }
+        assert_eq!(last, 1.);
+
+        // Chaining onto a half-played sequence starts a new timeline
+        let mut seq = Fixed(0f32).then(1., Fixed(1.));
+        seq.step(0.5);
+        let seq = seq.then(1., Fixed(2.));
+        assert_eq!(seq.progress(), Some(0.));
+    }
+
+    #[test]
+    fn loop_steps_agree_however_they_are_split() {
+        let lp = Animate::looping(Slide(0f32, 1., 0.3, 0.3), 0.7);
+        let mut small = lp.clone();
+        for _ in 0..10000 {
+            small.step(0.01);
+        }
+        assert!(close(small.now(), at(&lp, 100.)), "{} vs {}", small.now(), at(&lp, 100.));
+        // 100s is 142 whole cycles and 0.6s, which is past the end of the slide
+        assert!(close(at(&lp, 100.), 1.));
+        // and 0.25s later the next cycle is halfway through its slide
+        assert!(close(at(&lp, 100.25), 0.5));
+
+        let rep = Animate::repeat(Slide(0f32, 1., 1., 1.), 2., 3);
+        let mut small = rep.clone();
+        for _ in 0..700 {
+            small.step(0.01);
+        }
+        assert!(small.steady());
+        assert_eq!(small.now(), at(&rep, 7.));
+        assert_eq!(at(&rep, 4.5), 0.5);
+    }
+
+    /// Counts how many copies of it are alive, to catch double drops.
+    struct Tracked(::std::rc::Rc<::std::cell::Cell<i32>>);
+
+    impl Tracked {
+        fn new(live: &::std::rc::Rc<::std::cell::Cell<i32>>) -> Tracked {
+            live.set(live.get() + 1);
+            Tracked(live.clone())
+        }
+    }
+
+    impl Clone for Tracked {
+        fn clone(&self) -> Tracked { Tracked::new(&self.0) }
+    }
+
+    impl Drop for Tracked {
+        fn drop(&mut self) { self.0.set(self.0.get() - 1) }
+    }
+
+    impl Mixer<Tracked> for Option<Tracked> {
+        fn new() -> Self { None }
+        fn add(&mut self, v: &Tracked, _: Param) { *self = Some(v.clone()) }
+        fn close(self) -> Tracked { self.unwrap() }
+    }
+
+    impl Mixable for Tracked { type Mixer = Option<Tracked>; }
+
+    #[test]
+    fn panicking_step_func_drops_nothing_twice() {
+        use std::panic::{catch_unwind, AssertUnwindSafe};
+
+        let live = ::std::rc::Rc::new(::std::cell::Cell::new(0));
+        {
+            let mut anim = StepFunc(Arc::new(|v: Tracked, dt| {
+                if dt > 1. { panic!("too big a step") }
+                v
+            }), Tracked::new(&live));
+            anim.step(0.5);
+            assert_eq!(live.get(), 1);
+            let hook = ::std::panic::take_hook();
+            ::std::panic::set_hook(Box::new(|_| ()));
+            let res = catch_unwind(AssertUnwindSafe(|| anim.step(2.)));
+            ::std::panic::set_hook(hook);
+            assert!(res.is_err());
+            // The value the closure was given is gone, but the animation
+            // still holds its own
+            assert_eq!(live.get(), 1);
+            let now = anim.now();
+            assert_eq!(live.get(), 2);
+            drop(now);
+        }
+        assert_eq!(live.get(), 0);
+    }
+
+    #[test]
+    fn srgb_midpoint_is_brighter_than_a_naive_mix() {
+        let red = ColorSrgb([1., 0., 0., 1.]);
+        let green = ColorSrgb([0., 1., 0., 0.]);
+        let mid = ColorSrgb::linear(&red, &green, 0.5);
+        // Half of full intensity encodes to about 0.735, not 0.5
+        assert!(close(mid.0[0], 0.7354), "{:?}", mid);
+        assert!(close(mid.0[1], 0.7354), "{:?}", mid);
+        assert_eq!(mid.0[2], 0.);
+        assert!(mid.0[0] > 0.5 && mid.0[1] > 0.5);
+        // Alpha is already linear
+        assert_eq!(mid.0[3], 0.5);
+        // The ends come back unchanged
+        let end = ColorSrgb::linear(&red, &green, 1.);
+        for (a, b) in end.0.iter().zip(green.0.iter()) {
+            assert!(close(*a, *b));
+        }
+    }
+
+    #[test]
+    fn arrays_mix_per_component() {
+        let a = [1f32, -2., 3., 0.5];
+        let b = [0f32, 4., -1., 0.25];
+        let c = [2f32, 2., 2., 2.];
+        let mixed = <[f32; 4]>::mix(vec![(a, 0.2), (b, 0.3), (c, 0.5)].into_iter());
+        for i in 0..4 {
+            let one = f32::mix(vec![(a[i], 0.2), (b[i], 0.3), (c[i], 0.5)].into_iter());
+            assert_eq!(mixed[i], one);
+        }
+        let three = <[f32; 3]>::quadratic(&[0., 1., 2.], &[1., 1., 1.], &[2., 1., 0.], 0.25);
+        for i in 0..3 {
+            let a = [0f32, 1., 2.][i];
+            let c = [2f32, 1., 0.][i];
+            assert_eq!(three[i], f32::quadratic(&a, &1., &c, 0.25));
+        }
+    }
+
+    #[test]
+    fn slerp_turns_steadily_about_one_axis() {
+        let start = UnitQuaternion::identity();
+        let end = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 170f32.to_radians());
+        let mut last = 0.;
+        for i in 1..21 {
+            let t = i as f32 / 20.;
+            let q = UnitQuaternion::linear(&start, &end, t);
+            let axis = q.axis().unwrap();
+            assert!((axis.unwrap() - Vector3::y()).norm() < 1e-4, "axis {:?} at {}", axis, t);
+            let angle = q.angle();
+            assert!(angle > last, "{} after {} at {}", angle, last, t);
+            assert!((angle - t * 170f32.to_radians()).abs() < 1e-4, "{} at {}", angle, t);
+            last = angle;
+        }
+        // Past half a turn the short way round is the other way
+        let far = UnitQuaternion::from_axis_angle(&Vector3::y_axis(), 190f32.to_radians());
+        let half = UnitQuaternion::linear(&start, &far, 0.5);
+        assert!((half.angle() - 85f32.to_radians()).abs() < 1e-4);
+        assert!((half.axis().unwrap().unwrap() + Vector3::y()).norm() < 1e-4);
+    }
+
+    #[test]
+    fn sequence_steps_agree_however_they_are_split() {
+        let seq = AnimateSequence::start(5.)
+            .then(0.3, Slide(0f32, 1., 0.6, 0.6))
+            .then(0.45, BoundedLinear(2., 3., 0., 0.45))
+            .then(0.25, Fixed(4.));
+        // Sampled away from the ends of entries, where rounding in the sum
+        // of the small steps decides which side of the boundary they land on
+        for &end in &[0.2, 0.35, 0.5, 0.8, 0.9, 1.1, 1.5] {
+            let mut small = seq.clone();
+            let steps = (end / 0.05f32).round() as usize;
+            for _ in 0..steps {
+                small.step(0.05);
+            }
+            assert!(close(small.now(), at(&seq, end)), "{} vs {} at {}", small.now(), at(&seq, end), end);
+            assert!(close(small.elapsed, at_elapsed(&seq, end)));
+        }
+        // The time left over from a finished entry goes into the next one
+        let mut seq = seq;
+        seq.step(0.4);
+        assert_eq!(seq.queue.len(), 2);
+        assert!(close(seq.now(), 2. + 0.1 / 0.45));
+    }
+
+    fn at_elapsed(seq: &AnimateSequence<f32>, t: Time) -> Time {
+        let mut seq = seq.clone();
+        seq.step(t);
+        seq.elapsed
+    }
+
+    #[cfg(feature = "serde")]
+    #[test]
+    fn animations_survive_a_round_trip() {
+        fn round_trip(anim: &Animate<f32>) -> Animate<f32> {
+            let text = ::ron::ser::to_string(anim).unwrap();
+            ::ron::de::from_str(&text).unwrap()
+        }
+
+        let mut slide = Slide(0f32, 2., 1., 1.);
+        slide.step(0.25);
+        let mut seq = Fixed(0f32)
+            .then(0.5, Slide(0., 1., 1., 1.))
+            .then(1., Eased(1., 3., EasingKind::CubicOut, 0., 2.))
+            .then(1., Fixed(3.));
+        seq.step(0.75);
+        for anim in &[slide, seq] {
+            assert!(anim.serializes_exactly());
+            let back = round_trip(anim);
+            assert_eq!(back.progress(), anim.progress());
+            for i in 0..40 {
+                let t = i as f32 * 0.1;
+                assert_eq!(at(&back, t), at(anim, t), "at {}", t);
+            }
+        }
+    }
+
+    #[test]
+    fn retarget_halfway_carries_on_smoothly() {
+        let mut anim = BoundedLinear(0f32, 2., 0., 2.);
+        anim.step(1.);
+        let before = anim.now();
+        let speed = anim.velocity(VELOCITY_EPSILON);
+        anim.retarget(-1., 1.5);
+        // No jump in position, nor in speed
+        assert!(close(anim.now(), before));
+        assert!((anim.velocity(VELOCITY_EPSILON) - speed).abs() < 1e-2);
+        assert_eq!(anim.remaining_time(), Some(1.5));
+        let mut last = anim.now();
+        for _ in 0..20 {
+            anim.step(0.1);
+            // The curve is fastest at its end, at 2 * 2.75 / 1.5 per second
+            assert!((anim.now() - last).abs() < 0.37, "jumped from {} to {}", last, anim.now());
+            last = anim.now();
+        }
+        assert!(anim.steady());
+        assert_eq!(anim.now(), -1.);
+
+        // A settled animation slides to the new target
+        let mut fixed = Fixed(3f32);
+        fixed.retarget(5., 2.);
+        assert!(close(at(&fixed, 1.), 4.));
+        assert_eq!(at(&fixed, 2.5), 5.);
+    }
+
+    fn same_angle(a: WrappedAngle, degrees: f32) -> bool {
+        (a.0 - WrappedAngle::from_degrees(degrees).0).abs() < 1e-4
+    }
+
+    #[test]
+    fn angles_mix_the_short_way_round() {
+        let a = WrappedAngle::from_degrees(350.);
+        let b = WrappedAngle::from_degrees(10.);
+        for i in 0..11 {
+            let t = i as f32 / 10.;
+            // Through 0, never back through 180
+            assert!(same_angle(WrappedAngle::linear(&a, &b, t), 350. + 20. * t), "at {}", t);
+            assert!(same_angle(WrappedAngle::linear(&b, &a, t), 10. - 20. * t), "at {}", t);
+        }
+
+        // Three angles either side of the wrap at 180
+        let mixed = WrappedAngle::mix(vec![
+            (WrappedAngle::from_degrees(170.), 0.25),
+            (WrappedAngle::from_degrees(-170.), 0.5),
+            (WrappedAngle::from_degrees(160.), 0.25),
+        ].into_iter());
+        // 170, 190 and 160 weighted, which is 177.5
+        assert!(same_angle(mixed, 177.5), "{}", mixed.degrees());
+
+        let turned = UnitComplex::linear(
+            &UnitComplex::new(350f32.to_radians()),
+            &UnitComplex::new(10f32.to_radians()), 0.75);
+        assert!((turned.angle() - 5f32.to_radians()).abs() < 1e-4);
+    }
+
+    fn orthonormal(r: &Rotation3<f32>) -> bool {
+        let m = r.unwrap();
+        (m.transpose() * m - Matrix3::identity()).norm() < 1e-5 && (m.determinant() - 1.).abs() < 1e-5
+    }
+
+    #[test]
+    fn isometry_matrices_mix_to_proper_rotations() {
+        let a = IsometryMatrix3::new(Vector3::new(1f32, 0., 0.), Vector3::new(0.3, 0.2, -0.1));
+        // Nearly half a turn away from `a`
+        let b = IsometryMatrix3::new(Vector3::new(0f32, 2., 0.), Vector3::new(0., 3.1, 0.));
+        for i in 1..10 {
+            let t = i as f32 / 10.;
+            let m = IsometryMatrix3::linear(&a, &b, t);
+            assert!(orthonormal(&m.rotation), "{:?} at {}", m.rotation, t);
+            assert!((m.translation.vector - Vector3::new(1. - t, 2. * t, 0.)).norm() < 1e-5);
+        }
+        let mixed = IsometryMatrix3::mix(vec![(a, 0.2), (b, 0.3), (a, 0.5)].into_iter());
+        assert!(orthonormal(&mixed.rotation));
+
+        // The ends are exactly the inputs
+        assert_eq!(IsometryMatrix3::linear(&a, &b, 0.), a);
+        assert_eq!(IsometryMatrix3::linear(&a, &b, 1.), b);
+    }
+}
@@ -9,61 +9,542 @@ use flight::load;
 use flight::draw::{DrawParams, Painter, SolidStyle, PbrStyle, PbrMaterial};
 use flight::vr::{primary, secondary, VrMoment, ViveController, Trackable};
 
-use interact::{VrGuru};
+use interact::{VrGuru, GuruMemory, GuruDebugInfo, ObjectId, InteractionEvent, Anywhere, ControllerHistory, Hand, HANDS, Pulse, HapticRequest, limit_pulses};
+use error::{WorkbenchError, ResultExt};
+use observer::{ObserverSlot, WorkbenchObserver};
+use timing::FrameTiming;
+use animation::{Animate, Animation};
+use easing::EasingKind;
+use snap::SnapGrid;
+use pad::PadFilter;
+use sim::SimController;
+use debug::{DebugHud, CubeState};
+use scene::{PlaneSpawn, Scene};
+
+use std::time::Instant;
+use std::mem;
 
 pub const NEAR_PLANE: f64 = 0.1;
 pub const FAR_PLANE: f64 = 75.;
 pub const BACKGROUND: [f32; 4] = [0.529, 0.808, 0.980, 1.0];
 const PI: f32 = ::std::f32::consts::PI;
 const PI2: f32 = 2. * PI;
+const CONTROLLER_MESH: &'static str = "assets/controller.obj";
+/// Played when the laser moves onto a cube.
+const HOVER_PULSE: Pulse = Pulse { duration_us: 300, strength: 0.3 };
+/// Played when a cube is grabbed.
+const GRAB_PULSE: Pulse = Pulse { duration_us: 1500, strength: 1. };
+/// How close the controller must be to a cube to grab it directly.
+const TOUCH_MARGIN: f32 = 0.02;
+/// The closest to the controller that yanking brings a cube's surface.
+const YANK_DISTANCE: f32 = 0.1;
+/// The farthest from the controller that pushing sends a cube's surface.
+const PUSH_DISTANCE: f32 = 2.5;
+/// How far along the laser (in m) a cube moves per pad length swiped, when
+/// swiping slowly.
+const PAD_GAIN: f32 = 0.5;
+/// How much faster swipes (in pad lengths per second) move cubes
+/// disproportionately farther.
+const PAD_ACCELERATION: f32 = 0.5;
+/// How long the laser is drawn when it hits nothing.
+const LASER_STUB: f32 = 0.25;
+/// The radius of the dot drawn where the laser hits a cube.
+const LASER_DOT: f32 = 0.008;
+/// The farthest a single teleport can move the user.
+const MAX_TELEPORT: f32 = 6.;
+/// How long the view takes to brighten again after teleporting.
+const TELEPORT_FADE: f32 = 0.3;
+/// How long a released cube takes to slide onto the snap grid.
+const SNAP_TIME: f32 = 0.15;
+/// The smallest and largest a cube can be stretched to, as a radius.
+const MIN_RADIUS: f32 = 0.02;
+const MAX_RADIUS: f32 = 1.;
+/// Gravitational acceleration in m/s².
+const GRAVITY: f32 = 9.81;
+/// The fraction of its speed into the floor that a cube bounces back with.
+const RESTITUTION: f32 = 0.3;
+/// Cubes hitting the floor slower than this (in m/s) don't bounce at all.
+const BOUNCE_SPEED: f32 = 0.5;
+/// The fraction of a cube's sliding and spinning speed lost per second while
+/// it touches the floor.
+const FRICTION: f32 = 4.;
+/// Cubes on the floor moving slower than this (in m/s and rad/s) go to sleep.
+const SLEEP_SPEED: f32 = 0.05;
+/// How quickly a cube on the floor tips over onto its nearest face.
+const SETTLE_RATE: f32 = 8.;
+/// The longest physics step, so that a slow frame can't send cubes through
+/// the floor.
+const MAX_STEP: f32 = 0.05;
+/// How long (in seconds) a cube stays gone after leaving the kill volume.
+const RESPAWN_DELAY: f32 = 1.;
+/// How long a respawned cube takes to grow to full size.
+const SPAWN_TIME: f32 = 0.4;
+/// The scale respawned cubes grow from, since they are drawn with a
+/// similarity, which can't have zero scale.
+const SPAWN_SCALE: f32 = 1e-3;
+/// Half the width of the floor.
+const FLOOR_RADIUS: f32 = 5.;
+/// Held cubes bigger than this (as a radius) lift the laser of the hands
+/// holding them above the cube, if `App::set_lift_pointer` is on.
+const LIFT_RADIUS: f32 = 0.25;
+/// How far above the top of a held cube a lifted laser starts.
+const POINTER_LIFT: f32 = 0.05;
+
+/// Identifies a material in `AppMats`.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+pub struct MaterialId(usize);
+
+// The materials every `AppMats` starts with, in order
+pub const PLASTIC: MaterialId = MaterialId(0);
+pub const FLOOR: MaterialId = MaterialId(1);
+pub const DARK_PLASTIC: MaterialId = MaterialId(2);
+pub const BLUE_PLASTIC: MaterialId = MaterialId(3);
+pub const HOVER_PLASTIC: MaterialId = MaterialId(4);
+
+impl MaterialId {
+    /// Look up one of the materials every `AppMats` starts with by its name
+    /// in snake case, e.g. `"dark_plastic"`.
+    #[cfg(feature = "scene")]
+    pub fn named(name: &str) -> Option<MaterialId> {
+        match name {
+            "plastic" => Some(PLASTIC),
+            "floor" => Some(FLOOR),
+            "dark_plastic" => Some(DARK_PLASTIC),
+            "blue_plastic" => Some(BLUE_PLASTIC),
+            "hover_plastic" => Some(HOVER_PLASTIC),
+            _ => None,
+        }
+    }
+}
 
+/// The materials available to the app, looked up by `MaterialId`.
 pub struct AppMats<R: gfx::Resources> {
-    plastic: PbrMaterial<R>,
-    floor: PbrMaterial<R>,
-    dark_plastic: PbrMaterial<R>,
-    blue_plastic: PbrMaterial<R>,
+    mats: Vec<PbrMaterial<R>>,
 }
 
 impl<R: gfx::Resources> AppMats<R> {
     pub fn new<F: Factory<R> + FactoryExt<R>>(f: &mut F) -> Result<Self, Error> {
         use gfx::format::*;
-        Ok(AppMats {
-            plastic: PbrMaterial {
-                normal: Texture::<_, (R8_G8_B8_A8, Unorm)>::uniform_value(f, [0x80, 0x80, 0xFF, 0xFF])?,
-                albedo: Texture::<_, (R8_G8_B8_A8, Srgb)>::uniform_value(f, [0x60, 0x60, 0x60, 0xFF])?,
-                metalness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x00)?,
-                roughness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x20)?,
-            },
-            floor: PbrMaterial {
-                normal: Texture::<_, (R8_G8_B8_A8, Unorm)>::uniform_value(f, [0x80, 0x80, 0xFF, 0xFF])?,
-                albedo: Texture::<_, (R8_G8_B8_A8, Srgb)>::uniform_value(f, [0xA0, 0xA0, 0xA0, 0xFF])?,
-                metalness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0xFF)?,
-                roughness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x40)?,
-            },
-            dark_plastic: PbrMaterial {
-                normal: Texture::<_, (R8_G8_B8_A8, Unorm)>::uniform_value(f, [0x80, 0x80, 0xFF, 0xFF])?,
-                albedo: Texture::<_, (R8_G8_B8_A8, Srgb)>::uniform_value(f, [0x20, 0x20, 0x20, 0xFF])?,
-                metalness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x00)?,
-                roughness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x40)?,
-            },
-            blue_plastic: PbrMaterial {
-                normal: Texture::<_, (R8_G8_B8_A8, Unorm)>::uniform_value(f, [0x80, 0x80, 0xFF, 0xFF])?,
-                albedo: Texture::<_, (R8_G8_B8_A8, Srgb)>::uniform_value(f, [0x20, 0x20, 0xA0, 0xFF])?,
-                metalness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x00)?,
-                roughness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x40)?,
-            },
-        })
+        let mut mats = AppMats { mats: Vec::new() };
+        // PLASTIC
+        mats.insert(PbrMaterial {
+            normal: Texture::<_, (R8_G8_B8_A8, Unorm)>::uniform_value(f, [0x80, 0x80, 0xFF, 0xFF])?,
+            albedo: Texture::<_, (R8_G8_B8_A8, Srgb)>::uniform_value(f, [0x60, 0x60, 0x60, 0xFF])?,
+            metalness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x00)?,
+            roughness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x20)?,
+        });
+        // FLOOR
+        mats.insert(PbrMaterial {
+            normal: Texture::<_, (R8_G8_B8_A8, Unorm)>::uniform_value(f, [0x80, 0x80, 0xFF, 0xFF])?,
+            albedo: Texture::<_, (R8_G8_B8_A8, Srgb)>::uniform_value(f, [0xA0, 0xA0, 0xA0, 0xFF])?,
+            metalness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0xFF)?,
+            roughness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x40)?,
+        });
+        // DARK_PLASTIC
+        mats.insert(PbrMaterial {
+            normal: Texture::<_, (R8_G8_B8_A8, Unorm)>::uniform_value(f, [0x80, 0x80, 0xFF, 0xFF])?,
+            albedo: Texture::<_, (R8_G8_B8_A8, Srgb)>::uniform_value(f, [0x20, 0x20, 0x20, 0xFF])?,
+            metalness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x00)?,
+            roughness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x40)?,
+        });
+        // BLUE_PLASTIC
+        mats.insert(PbrMaterial {
+            normal: Texture::<_, (R8_G8_B8_A8, Unorm)>::uniform_value(f, [0x80, 0x80, 0xFF, 0xFF])?,
+            albedo: Texture::<_, (R8_G8_B8_A8, Srgb)>::uniform_value(f, [0x20, 0x20, 0xA0, 0xFF])?,
+            metalness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x00)?,
+            roughness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x40)?,
+        });
+        // HOVER_PLASTIC
+        mats.insert(PbrMaterial {
+            normal: Texture::<_, (R8_G8_B8_A8, Unorm)>::uniform_value(f, [0x80, 0x80, 0xFF, 0xFF])?,
+            albedo: Texture::<_, (R8_G8_B8_A8, Srgb)>::uniform_value(f, [0x30, 0x30, 0x50, 0xFF])?,
+            metalness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x00)?,
+            roughness: Texture::<_, (R8, Unorm)>::uniform_value(f, 0x30)?,
+        });
+        Ok(mats)
+    }
+
+    /// Add a material, returning the id to draw with it.
+    pub fn insert(&mut self, mat: PbrMaterial<R>) -> MaterialId {
+        self.mats.push(mat);
+        MaterialId(self.mats.len() - 1)
+    }
+
+    /// Get a material. Panics if `id` came from another `AppMats`.
+    pub fn get(&self, id: MaterialId) -> &PbrMaterial<R> {
+        &self.mats[id.0]
+    }
+
+    fn get_mut(&mut self, id: MaterialId) -> &mut PbrMaterial<R> {
+        &mut self.mats[id.0]
     }
 }
 
+/// Draw `mesh` with `mat` instead of its own material.
+fn draw_with_mat<R: gfx::Resources, C: gfx::CommandBuffer<R>>(
+    pbr: &mut Painter<R, PbrStyle<R>>,
+    ctx: &mut DrawParams<R, C>,
+    pose: Similarity3<f32>,
+    mesh: &mut PbrMesh<R>,
+    mat: &mut PbrMaterial<R>,
+) {
+    lend_mat(mesh, |m| &mut m.mat, mat, |mesh| {
+        pbr.draw(ctx, na::convert(pose), mesh);
+    });
+}
+
+/// Call `f` with `mat` in place of the material of `owner` that `slot`
+/// picks. The two are swapped for the call and swapped back after, so that
+/// nothing needs cloning.
+fn lend_mat<T, M, S, F>(owner: &mut T, slot: S, mat: &mut M, f: F)
+    where S: Fn(&mut T) -> &mut M, F: FnOnce(&T)
+{
+    mem::swap(slot(owner), mat);
+    f(owner);
+    mem::swap(slot(owner), mat);
+}
+
 pub struct Model {
     cubes: Vec<CubeModel>,
+    /// The planes of the scene, where they are now, for cubes to land on.
+    planes: Vec<PlaneSpawn>,
+    /// Cubes outside this box, in stage space, are removed and respawned.
+    pub kill_min: Point3<f32>,
+    pub kill_max: Point3<f32>,
+}
+
+impl Model {
+    /// Build the cubes of a scene, where they first appear.
+    pub fn new(scene: &Scene) -> Model {
+        Model {
+            cubes: scene.cubes.iter().map(|spawn| {
+                CubeModel {
+                    grabbed: None,
+                    grab_start: None,
+                    pos: spawn.pose,
+                    vel: na::zero(),
+                    ang_vel: na::zero(),
+                    sleeping: false,
+                    pointed: [false; 2],
+                    radius: spawn.radius,
+                    mat: spawn.mat,
+                    snap: None,
+                    spawn: spawn.pose,
+                    spawn_radius: spawn.radius,
+                    dead: None,
+                    grow: None,
+                    grabbable: spawn.grabbable,
+                }
+            }).collect(),
+            planes: scene.planes.clone(),
+            // A little bigger than the floor
+            kill_min: Point3::new(-6., -2., -6.),
+            kill_max: Point3::new(6., 20., 6.),
+        }
+    }
+
+    /// Step the physics, then remove cubes that have left the kill volume
+    /// and respawn the ones that have been gone long enough.
+    pub fn update(&mut self, dt: f32, stage: &Isometry3<f32>, observer: &ObserverSlot) {
+        self.simulate(dt, stage);
+        let (min, max) = (self.kill_min.coords, self.kill_max.coords);
+        for cube in &mut self.cubes {
+            if let Some(left) = cube.dead {
+                if left > dt {
+                    cube.dead = Some(left - dt);
+                } else {
+                    cube.respawn();
+                    observer.notify(|o| o.object_spawned("cube"));
+                }
+            } else if cube.grabbed.is_none() {
+                let p = (stage.inverse() * Point3::from_coordinates(cube.pos.translation.vector)).coords;
+                let inside = (0..3).all(|i| p[i] >= min[i] && p[i] <= max[i]);
+                if !inside {
+                    cube.kill();
+                }
+            }
+        }
+    }
+
+    /// Move every cube that is not held, e.g. to keep them in place in the
+    /// world when the user teleports.
+    fn shift(&mut self, by: &Vector3<f32>) {
+        for cube in &mut self.cubes {
+            if cube.grabbed.is_none() {
+                if let Some(snap) = cube.snap.take() {
+                    cube.pos = snap.end_value().unwrap_or(cube.pos);
+                }
+                cube.pos.translation.vector += by;
+            }
+            cube.spawn.translation.vector += by;
+        }
+        for plane in &mut self.planes {
+            plane.pose.translation.vector += by;
+        }
+    }
+
+    /// Step the physics of every cube that is not held. The floor is the
+    /// `y=0` plane of `stage`, and cubes over a plane of the scene land on
+    /// that instead.
+    pub fn simulate(&mut self, dt: f32, stage: &Isometry3<f32>) {
+        let dt = dt.min(MAX_STEP);
+        let up = stage.rotation * Vector3::y();
+        let planes = &self.planes;
+        for cube in &mut self.cubes {
+            if cube.dead.is_some() { continue }
+            if let Some(mut grow) = cube.grow.take() {
+                grow.step(dt);
+                if !grow.steady() {
+                    cube.grow = Some(grow);
+                }
+            } else if let Some(mut snap) = cube.snap.take() {
+                // Once in place, sleep so that the cube stays on the grid,
+                // even if that is in the air
+                snap.step(dt);
+                cube.pos = snap.now();
+                if snap.steady() {
+                    cube.sleeping = true;
+                } else {
+                    cube.snap = Some(snap);
+                }
+            } else if cube.grabbed.is_none() && !cube.sleeping {
+                let floor = support(planes, &cube.pos.translation.vector, stage);
+                cube.simulate(dt, &up, &floor);
+            }
+        }
+    }
+}
+
+/// Find a point on whatever `center` would land on: the highest plane
+/// below it that it is over, or else the floor of `stage`.
+fn support(planes: &[PlaneSpawn], center: &Vector3<f32>, stage: &Isometry3<f32>) -> Vector3<f32> {
+    let up = stage.rotation * Vector3::y();
+    let mut floor = stage.translation.vector;
+    for plane in planes {
+        let local = plane.pose.inverse() * Point3::from_coordinates(*center);
+        let over = local.x.abs() <= plane.radius && local.z.abs() <= plane.radius && local.y >= 0.;
+        if over && (plane.pose.translation.vector - floor).dot(&up) > 0. {
+            floor = plane.pose.translation.vector;
+        }
+    }
+    floor
+}
+
+/// How a cube is being held.
+#[derive(Clone, Copy)]
+enum Grab {
+    /// Held by one controller, at a fixed offset from it.
+    One(Hand, Isometry3<f32>),
+    /// Stretched between both controllers. Remembers the cube, the midpoint
+    /// between the controllers, and the vector from the primary to the
+    /// secondary controller as they were when the stretch began.
+    Two {
+        start: Isometry3<f32>,
+        radius: f32,
+        mid: Point3<f32>,
+        axis: Vector3<f32>,
+    },
+}
+
+impl Grab {
+    fn holds(&self, hand: Hand) -> bool {
+        match *self {
+            Grab::One(h, _) => h == hand,
+            Grab::Two { .. } => true,
+        }
+    }
 }
 
 pub struct CubeModel {
-    grabbed: Option<Isometry3<f32>>,
+    grabbed: Option<Grab>,
+    grab_start: Option<(Instant, Vector3<f32>)>,
     pos: Isometry3<f32>,
+    vel: Vector3<f32>,
+    ang_vel: Vector3<f32>,
+    /// Set once the cube comes to rest, so that it stops jittering.
+    sleeping: bool,
+    /// Set for each hand whose laser is on the cube.
+    pointed: [bool; 2],
     radius: f32,
+    /// What the cube is made of when it isn't held or pointed at.
+    mat: MaterialId,
+    /// Slides the cube onto the snap grid after it is let go.
+    snap: Option<Animate<Isometry3<f32>>>,
+    /// Where the cube first appeared, and how big it was, so that it can
+    /// respawn there.
+    spawn: Isometry3<f32>,
+    spawn_radius: f32,
+    /// The seconds left until the cube respawns, if it's gone.
+    dead: Option<f32>,
+    /// Cubes that can't be grabbed still stop lasers, but are never
+    /// pointed at or touched.
+    grabbable: bool,
+    /// Grows the cube after it respawns, as a scale relative to the radius.
+    grow: Option<Animate<f32>>,
+}
+
+impl CubeModel {
+    /// Remove the cube until it respawns.
+    fn kill(&mut self) {
+        self.dead = Some(RESPAWN_DELAY);
+        self.snap = None;
+        self.grow = None;
+        self.pointed = [false; 2];
+    }
+
+    /// Put the cube back where it first appeared, growing in from nothing.
+    fn respawn(&mut self) {
+        self.dead = None;
+        self.pos = self.spawn;
+        self.radius = self.spawn_radius;
+        self.vel = na::zero();
+        self.ang_vel = na::zero();
+        self.sleeping = false;
+        self.grow = Some(Animate::Eased(SPAWN_SCALE, 1., EasingKind::BackOut, 0., SPAWN_TIME));
+    }
+
+    /// Get the pose to draw the cube with, scaled to its size.
+    fn draw_pose(&self) -> Similarity3<f32> {
+        match self.grow {
+            Some(ref grow) => Similarity3::from_isometry(self.pos, grow.now() * self.radius),
+            None => Similarity3::from_isometry(self.pos, self.radius),
+        }
+    }
+
+    /// Apply gravity, move the cube, and keep it above the floor given by
+    /// its normal `up` and any point on it.
+    fn simulate(&mut self, dt: f32, up: &Vector3<f32>, floor: &Vector3<f32>) {
+        self.vel -= up * (GRAVITY * dt);
+        self.pos = Isometry3::from_parts(
+            Translation3::from_vector(self.pos.translation.vector + self.vel * dt),
+            UnitQuaternion::new(self.ang_vel * dt) * self.pos.rotation,
+        );
+
+        // Find how far the lowest corner or edge is below the center
+        let local_up = self.pos.rotation.inverse() * up;
+        let extent = self.radius * (local_up.x.abs() + local_up.y.abs() + local_up.z.abs());
+        let height = (self.pos.translation.vector - floor).dot(up);
+        if height > extent { return }
+
+        // Push out of the floor, bounce, and slow down
+        self.pos.translation.vector += up * (extent - height);
+        let into = self.vel.dot(up);
+        if into < 0. {
+            let bounce = if -into > BOUNCE_SPEED { RESTITUTION } else { 0. };
+            self.vel -= up * (into * (1. + bounce));
+        }
+        let keep = (1. - FRICTION * dt).max(0.);
+        let normal = up * self.vel.dot(up);
+        self.vel = normal + (self.vel - normal) * keep;
+        self.ang_vel *= keep;
+
+        // Tip over onto whichever face is closest to facing down, since
+        // cubes can't balance on an edge
+        let (x, y, z) = (local_up.x.abs(), local_up.y.abs(), local_up.z.abs());
+        let face = if x >= y && x >= z {
+            Vector3::x() * local_up.x.signum()
+        } else if y >= z {
+            Vector3::y() * local_up.y.signum()
+        } else {
+            Vector3::z() * local_up.z.signum()
+        };
+        let tip = UnitQuaternion::rotation_between(&(self.pos.rotation * face), up)
+            .unwrap_or(UnitQuaternion::identity());
+        let resting = self.vel.norm() < SLEEP_SPEED
+            && self.ang_vel.norm() < SLEEP_SPEED
+            && tip.angle() < 0.01;
+        // Once resting, finish tipping at once
+        let settle = if resting { 1. } else { (SETTLE_RATE * dt).min(1.) };
+        self.pos.rotation = UnitQuaternion::new(tip.scaled_axis() * settle) * self.pos.rotation;
+
+        if resting {
+            // Sit flat on the floor rather than on an edge a little above it.
+            // The center is `extent` above the floor since being pushed out.
+            self.pos.translation.vector += up * (self.radius - extent);
+            self.vel = na::zero();
+            self.ang_vel = na::zero();
+            self.sleeping = true;
+        }
+    }
+}
+
+/// Aims teleports with the secondary pad. The same pad yanks and pushes
+/// cubes, so once the thumb is used on a cube it can't teleport until it is
+/// lifted.
+struct TeleportAim {
+    /// Where the user will teleport to when they lift their thumb.
+    target: Option<Point3<f32>>,
+    /// Set while the thumb stays on the pad after the secondary hand pointed
+    /// at or held a cube, or the thumb swiped.
+    cancelled: bool,
+}
+
+impl TeleportAim {
+    fn new() -> TeleportAim {
+        TeleportAim {
+            target: None,
+            cancelled: false,
+        }
+    }
+
+    /// Aim at `floor` while the thumb is `touched` on the pad, and get where
+    /// to go once it's lifted. `busy` is set if the secondary hand is
+    /// pointing at or holding a cube, or the thumb just swiped.
+    fn update(&mut self, touched: bool, busy: bool, floor: Option<Point3<f32>>) -> Option<Point3<f32>> {
+        if !touched {
+            self.cancelled = false;
+            return self.target.take();
+        }
+        self.cancelled = self.cancelled || busy;
+        self.target = if self.cancelled { None } else { floor };
+        None
+    }
+}
+
+/// Everything that happens in a frame besides drawing it: the controllers,
+/// what they do to the cubes, and what gets reported. It needs no GPU, so
+/// whole sessions can be scripted, e.g. with `SimController`s.
+struct Session {
+    primary: ViveController,
+    secondary: ViveController,
+    primary_history: ControllerHistory,
+    secondary_history: ControllerHistory,
+    observer: ObserverSlot,
+    started: bool,
+    /// Whether the controllers were tracked last frame, so that losing them
+    /// is reported once rather than every frame.
+    tracking: bool,
+    timing: FrameTiming,
+    haptics: Vec<HapticRequest>,
+    guru_memory: GuruMemory,
+    /// Filters the touchpads, indexed by `Hand::index`.
+    pads: [PadFilter; 2],
+    events: Vec<InteractionEvent>,
+    /// Moves the world relative to the tracked play area, so that the user
+    /// can teleport. Only ever a translation, so the world never tilts or
+    /// turns.
+    world_offset: Isometry3<f32>,
+    /// Where the user will teleport to when they lift their thumb.
+    teleport_aim: TeleportAim,
+    /// How dark the view is, from `0` to `1`.
+    fade: Animate<f32>,
+    /// Where released cubes are placed, relative to the stage, if anywhere.
+    snap_grid: Option<SnapGrid>,
+    /// Start the lasers of hands holding big cubes above them.
+    lift_pointer: bool,
+}
+
+/// What a frame of a `Session` leaves for `App` to draw.
+struct FrameView {
+    stage: Isometry3<f32>,
+    /// How bright the view is, from `0` to `1`.
+    light: f32,
+    /// What each cube is drawn with, or `None` while it's gone.
+    cube_mats: Vec<Option<MaterialId>>,
+    /// Each hand's laser: where it starts, how far it reaches and what it
+    /// hits, and how far along it is first drawn.
+    lasers: Vec<(Hand, Point3<f32>, Option<f32>, Option<ObjectId>, f32)>,
+    debug_info: Option<GuruDebugInfo>,
 }
 
 pub struct App<R: gfx::Resources> {
@@ -71,11 +552,20 @@ pub struct App<R: gfx::Resources> {
     pbr: Painter<R, PbrStyle<R>>,
     controller: PbrMesh<R>,
     line: Mesh<R, VertC, ()>,
+    /// The laser when it hits the floor or nothing.
+    dim_line: Mesh<R, VertC, ()>,
+    /// Marks where the laser hits a cube.
+    dot: Mesh<R, VertC, ()>,
     floor: PbrMesh<R>,
     cube: PbrMesh<R>,
     mats: AppMats<R>,
-    primary: ViveController,
-    secondary: ViveController,
+    session: Session,
+    debug: DebugHud<R>,
+    /// Show the debug overlay even without holding the secondary menu
+    /// button.
+    debug_pinned: bool,
+    /// What `model` builds, and the planes drawn with it.
+    scene: Scene,
 }
 
 fn plane(rad: f32) -> MeshSource<VertN, ()> {
@@ -94,6 +584,34 @@ fn plane(rad: f32) -> MeshSource<VertN, ()> {
     }
 }
 
+/// An octahedron with its corners `rad` from the center, all one color.
+fn octahedron(rad: f32, color: [f32; 3]) -> MeshSource<VertC, ()> {
+    let corners = [
+        [rad, 0., 0.], [-rad, 0., 0.],
+        [0., rad, 0.], [0., -rad, 0.],
+        [0., 0., rad], [0., 0., -rad],
+    ];
+    let mut verts = Vec::with_capacity(24);
+    for &x in &[0, 1] {
+        for &y in &[2, 3] {
+            for &z in &[4, 5] {
+                // Wind each face counterclockwise seen from outside, which
+                // flips with every negative axis
+                let face = if (x + y + z) % 2 == 0 { [x, y, z] } else { [x, z, y] };
+                for &i in &face {
+                    verts.push(VertC { pos: corners[i], color: color });
+                }
+            }
+        }
+    }
+    MeshSource {
+        verts: verts,
+        inds: Indexing::All,
+        prim: Primitive::TriangleList,
+        mat: (),
+    }
+}
+
 fn bevel_cube(rad: f32, bev: f32) -> MeshSource<VertN, ()> {
     let verts = vec![
         VertN { pos: [rad, -(rad - bev), -(rad - bev)], norm: [1., 0., 0.] },
@@ -142,42 +660,481 @@ fn bevel_cube(rad: f32, bev: f32) -> MeshSource<VertN, ()> {
     }
 }
 
-impl<R: gfx::Resources> App<R> {
-    pub fn model(&self) -> Model {
-        Model {
-            cubes: (0i32..10).map(|i| {
-                let rad = 0.2 * (1. - i as f32 / 15.);
-                let theta = (i as f32) / 5. * PI;
-                CubeModel {
-                    grabbed: None,
-                    pos: Isometry3::from_parts(
-                        Translation3::new(theta.sin() * 1., 0., theta.cos() * 1.),
-                        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), theta)
-                    ),
-                    radius: rad,
+/// Take the answer to a query, reporting a failure to resolve it as a miss.
+fn resolved<T>(reply: &Anywhere<Option<T>>, what: &str, observer: &ObserverSlot) -> Option<T> {
+    match reply.get(what) {
+        Ok(hit) => hit,
+        Err(e) => {
+            report(&e, observer);
+            None
+        },
+    }
+}
+
+/// Log an error, only as a warning if it may go away by itself, and tell the
+/// observer about it.
+fn report(err: &WorkbenchError, observer: &ObserverSlot) {
+    if err.is_transient() {
+        warn!("{}", err);
+    } else {
+        error!("{}", err);
+    }
+    observer.report(err);
+}
+
+/// Yank and push: swiping down or up the pad slides a cube along the laser,
+/// at a speed set by how fast the swipe is. Get the distance along the laser
+/// to move a cube hit at `toi` to, after the thumb moved `pad_delta` pad
+/// lengths in a frame of `dt` seconds.
+fn yank_push(toi: f32, pad_delta: f32, dt: f32) -> f32 {
+    if dt <= 0. || pad_delta == 0. {
+        return toi;
+    }
+    let swipe = pad_delta / dt;
+    let speed = swipe * (1. + swipe.abs() * PAD_ACCELERATION) * PAD_GAIN;
+    // Never yank closer or push farther than the limits, but don't snap
+    // cubes already past them
+    (toi + speed * dt)
+        .max(YANK_DISTANCE.min(toi))
+        .min(PUSH_DISTANCE.max(toi))
+}
+
+/// Get where the laser of a hand holding `cube` starts when lifted above
+/// it: clear of the cube however it's turned.
+fn lifted_origin(cube: &CubeModel, up: &Vector3<f32>) -> Point3<f32> {
+    let corner = cube.radius * 3f32.sqrt();
+    Point3::from_coordinates(cube.pos.translation.vector + up * (corner + POINTER_LIFT))
+}
+
+/// Tell the observer if the previous frame ran over its budget.
+fn check_budget(timing: &FrameTiming, observer: &ObserverSlot) {
+    if let Some(overrun) = timing.overrun() {
+        debug!("{}", WorkbenchError::BudgetExceeded(overrun));
+        observer.notify(|o| o.frame_budget_exceeded(overrun.class));
+    }
+}
+
+impl Session {
+    fn new() -> Session {
+        Session {
+            primary: ViveController {
+                is: primary(),
+                .. Default::default()
+            },
+            secondary: ViveController {
+                is: secondary(),
+                .. Default::default()
+            },
+            primary_history: ControllerHistory::new(),
+            secondary_history: ControllerHistory::new(),
+            observer: ObserverSlot::new(),
+            started: false,
+            tracking: true,
+            timing: FrameTiming::new(),
+            haptics: Vec::new(),
+            guru_memory: GuruMemory::new(),
+            pads: [PadFilter::new(), PadFilter::new()],
+            events: Vec::new(),
+            world_offset: na::one(),
+            teleport_aim: TeleportAim::new(),
+            fade: Animate::Fixed(0.),
+            snap_grid: None,
+            lift_pointer: false,
+        }
+    }
+
+    /// Build the cubes of a scene, where they first appear.
+    fn load(&self, scene: &Scene) -> Model {
+        let model = Model::new(scene);
+        self.observer.notify(|o| o.scene_loaded(model.cubes.len()));
+        model
+    }
+
+    fn controller(&self, hand: Hand) -> &ViveController {
+        match hand {
+            Hand::Primary => &self.primary,
+            Hand::Secondary => &self.secondary,
+        }
+    }
+
+    fn history(&self, hand: Hand) -> &ControllerHistory {
+        match hand {
+            Hand::Primary => &self.primary_history,
+            Hand::Secondary => &self.secondary_history,
+        }
+    }
+
+    /// Start holding a cube in one hand, wherever it is now.
+    fn grab_one(&self, hand: Hand, cube: &CubeModel) -> Grab {
+        Grab::One(hand, self.controller(hand).pose().inverse() * cube.pos)
+    }
+
+    /// Start stretching a cube between both hands, wherever it is now.
+    fn grab_two(&self, cube: &CubeModel) -> Grab {
+        let (a, b) = (self.primary.origin(), self.secondary.origin());
+        Grab::Two {
+            start: cube.pos,
+            radius: cube.radius,
+            mid: na::center(&a, &b),
+            axis: b - a,
+        }
+    }
+
+    /// Move a held cube along with the controllers holding it.
+    fn follow(&self, grab: &Grab, cube: &mut CubeModel) {
+        match *grab {
+            Grab::One(hand, off) => cube.pos = self.controller(hand).pose() * off,
+            Grab::Two { start, radius, mid, axis } => {
+                // Scale and turn the cube about the midpoint, as the
+                // controllers are pulled apart and turned around it
+                let (a, b) = (self.primary.origin(), self.secondary.origin());
+                let now = b - a;
+                let scale = (now.norm() / axis.norm().max(0.001))
+                    .max(MIN_RADIUS / radius)
+                    .min(MAX_RADIUS / radius);
+                let rot = UnitQuaternion::rotation_between(&axis, &now)
+                    .unwrap_or(UnitQuaternion::identity());
+                let offset = start.translation.vector - mid.coords;
+                cube.pos = Isometry3::from_parts(
+                    Translation3::from_vector(na::center(&a, &b).coords + rot * offset * scale),
+                    rot * start.rotation,
+                );
+                cube.radius = radius * scale;
+            },
+        }
+    }
+
+    /// Move the user across the floor to a point in the stage plane,
+    /// standing where the secondary controller is held now.
+    fn teleport(&mut self, to: Point3<f32>, stage: &Isometry3<f32>, model: &mut Model) {
+        let up = stage.rotation * Vector3::y();
+        let origin = self.secondary.origin();
+        let from = origin - up * (origin - Point3::from_coordinates(stage.translation.vector)).dot(&up);
+        let mut by = to - from;
+        let mut distance = by.norm();
+        if distance > MAX_TELEPORT {
+            by *= MAX_TELEPORT / distance;
+            distance = MAX_TELEPORT;
+        }
+
+        // The user stays put in the play area, so move the world instead
+        self.world_offset = Translation3::from_vector(-by) * self.world_offset;
+        model.shift(&-by);
+        self.fade = Animate::Eased(1., 0., EasingKind::QuadIn, 0., TELEPORT_FADE);
+        self.observer.notify(|o| o.teleport(distance));
+        self.events.push(InteractionEvent::Teleport { distance: distance });
+    }
+
+    /// Run a frame, once the controllers and `timing` are up to date.
+    /// `tracked` is the pose of the floor, and `tracking` is false if the
+    /// controllers couldn't be updated.
+    fn update(&mut self, tracked: Isometry3<f32>, tracking: bool, debug_pinned: bool, model: &mut Model) -> FrameView {
+        if !self.started {
+            self.started = true;
+            self.observer.notify(|o| o.session_started());
+        }
+
+        check_budget(&self.timing, &self.observer);
+        self.haptics.clear();
+        self.events.clear();
+        self.fade.step(self.timing.delta);
+        let time = self.timing.elapsed;
+        let dt = self.timing.delta;
+
+        if tracking {
+            self.primary_history.push(time, self.primary.pose());
+            self.secondary_history.push(time, self.secondary.pose());
+            for &h in HANDS.iter() {
+                let pad = [self.controller(h).pad[0], self.controller(h).pad[1]];
+                self.pads[h.index()].update(pad, dt);
+            }
+        } else {
+            self.primary_history.clear();
+            self.secondary_history.clear();
+            for pad in &mut self.pads {
+                pad.reset();
+            }
+            if self.tracking {
+                let err = WorkbenchError::VrRuntime {
+                    transient: true,
+                    detail: "a not vive-like controller is connected".into(),
+                };
+                report(&err, &self.observer);
+            }
+        }
+        self.tracking = tracking;
+
+        let stage = self.world_offset * tracked;
+        // Dim everything while fading
+        let light = 1. - self.fade.now().max(0.).min(1.);
+
+        // Update physics
+        model.update(dt, &stage, &self.observer);
+
+        // Update cubes
+        let debug = debug_pinned || self.secondary.menu;
+        let mut guru = VrGuru::new(&self.primary, &self.secondary); 
+        if self.lift_pointer {
+            let up = stage.rotation * Vector3::y();
+            for &h in HANDS.iter() {
+                let big = model.cubes.iter().find(|c| {
+                    c.radius > LIFT_RADIUS && c.grabbed.map_or(false, |grab| grab.holds(h))
+                });
+                if let Some(cube) = big {
+                    guru.hand(h).lift_ray(lifted_origin(cube, &up));
                 }
-            }).collect(),
+            }
+        }
+        // A hand that holds a cube can't reach into others, so they don't
+        // hide its laser either
+        for &h in HANDS.iter() {
+            if model.cubes.iter().any(|c| c.grabbed.map_or(false, |grab| grab.holds(h))) {
+                guru.hand(h).block_touching();
+            }
+        }
+        let cube_partials: Vec<_> = model.cubes
+            .iter_mut()
+            .enumerate()
+            .map(|(id, model)| {
+                // Let go with any hand whose trigger is released
+                let held = |h: Hand| self.controller(h).trigger > 0.5;
+                let grab = model.grabbed;
+                let released = match grab {
+                    Some(Grab::Two { .. }) => match (held(Hand::Primary), held(Hand::Secondary)) {
+                        (true, true) => None,
+                        (false, false) => Some(Hand::Primary),
+                        (p, _) => {
+                            // Carry on with the other hand, from where the
+                            // cube is now so that it doesn't jump
+                            let h = if p { Hand::Primary } else { Hand::Secondary };
+                            model.grabbed = Some(self.grab_one(h, model));
+                            None
+                        },
+                    },
+                    Some(Grab::One(h, _)) => if held(h) { None } else { Some(h) },
+                    None => None,
+                };
+                if let Some(h) = released {
+                    model.grabbed = None;
+                    model.sleeping = false;
+                    if let Some(grid) = self.snap_grid {
+                        // Slide onto the grid, but never into the floor
+                        let grid = SnapGrid { origin: stage * grid.origin, .. grid };
+                        let mut to = grid.snap_isometry(&model.pos);
+                        let up = stage.rotation * Vector3::y();
+                        let height = (to.translation.vector - stage.translation.vector).dot(&up);
+                        if height < model.radius {
+                            to.translation.vector += up * (model.radius - height);
+                        }
+                        model.vel = na::zero();
+                        model.ang_vel = na::zero();
+                        model.snap = Some(Animate::Eased(model.pos, to, EasingKind::QuadOut, 0., SNAP_TIME));
+                    } else {
+                        // Throw
+                        let v = self.history(h).linear_velocity();
+                        let w = self.history(h).angular_velocity();
+                        let arm = model.pos.translation.vector - self.controller(h).pose().translation.vector;
+                        model.vel = v + w.cross(&arm);
+                        model.ang_vel = w;
+                    }
+                    if let Some((start, from)) = model.grab_start.take() {
+                        let elapsed = start.elapsed();
+                        let duration = elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 * 1e-9;
+                        let distance = (model.pos.translation.vector - from).norm();
+                        self.observer.notify(|o| o.grab_completed(duration, distance));
+                    }
+                }
+
+                let cuboid = Cuboid3::new(Vector3::from_element(model.radius));
+                let mut queries = Vec::with_capacity(HANDS.len());
+                // Cubes that are gone can't be reached
+                let hands: &[Hand] = if model.dead.is_some() { &[] } else { &HANDS };
+                for &h in hands.iter() {
+                    let g = guru.hand(h);
+                    if model.grabbed.map_or(false, |grab| grab.holds(h)) {
+                        let reach = (model.pos.translation.vector - g.data.origin().coords).norm()
+                            + model.radius * 2.;
+                        g.hold(id, &model.pos, &cuboid, reach);
+                    } else {
+                        g.laser(Some(id), &model.pos, &cuboid);
+                    }
+                    if !model.grabbable { continue }
+                    let reply = g.pointing(
+                        Some(id),
+                        &model.pos,
+                        &cuboid,
+                        true,
+                        if model.pointed[h.index()] { None } else { Some(HOVER_PULSE) });
+                    let touch = g.touching(
+                        Some(id),
+                        &model.pos,
+                        &cuboid,
+                        TOUCH_MARGIN,
+                        true);
+                    queries.push((h, reply, touch));
+                }
+                move |app: &mut Session| {
+                    if model.dead.is_some() { return None }
+                    for &(h, ref reply, ref touch) in queries.iter() {
+                        let hit = resolved(reply, "pointing reply was not applied", &app.observer);
+                        let touched = resolved(touch, "touching reply was not applied", &app.observer);
+                        model.pointed[h.index()] = hit.is_some();
+                        let (pointing, trigger, trigger_delta) = {
+                            let c = app.controller(h);
+                            (c.pointing(), c.trigger, c.trigger_delta)
+                        };
+                        let pad_delta = app.pads[h.index()].delta()[1];
+                        if let Some(hit) = hit {
+                            let toi = yank_push(hit.toi, pad_delta, dt);
+                            if toi != hit.toi {
+                                // Keep the spot the laser hit on the laser
+                                let to = hit.point + pointing * (toi - hit.toi);
+                                model.pos.translation.vector = to.coords - model.pos.rotation * hit.local_point.coords;
+                                model.sleeping = false;
+                            }
+                        }
+
+                        // Grab, or stretch if the other hand already has it
+                        if (hit.is_some() || touched.is_some())
+                        && trigger > 0.5 
+                        && trigger - trigger_delta < 0.5 {
+                            let grab = model.grabbed;
+                            model.snap = None;
+                            model.grow = None;
+                            model.grabbed = match grab {
+                                None => {
+                                    model.grab_start = Some((Instant::now(), model.pos.translation.vector));
+                                    Some(app.grab_one(h, model))
+                                },
+                                Some(Grab::One(other, _)) if other != h => Some(app.grab_two(model)),
+                                grab => grab,
+                            };
+                            app.haptics.push(HapticRequest::new(h, GRAB_PULSE));
+                        }
+                    }
+
+                    // Update position
+                    if let Some(grab) = model.grabbed {
+                        app.follow(&grab, model);
+                    }
+
+                    // Highlight held cubes, and cubes a laser is on
+                    Some(if model.grabbed.is_some() {
+                        BLUE_PLASTIC
+                    } else if model.pointed.iter().any(|&p| p) {
+                        HOVER_PLASTIC
+                    } else {
+                        model.mat
+                    })
+                }
+            })
+            .collect();
+        let lasers: Vec<_> = HANDS.iter().map(|&h| {
+            let g = guru.hand(h);
+            // Reaching into a cube takes priority over pointing at one
+            if g.touching_any() {
+                g.block_pointing();
+            }
+            g.laser(None, &stage, &Plane::new(Vector3::y()));
+            (h, g.ray().origin, g.laser_toi, g.laser_id, g.laser_start)
+        }).collect();
+        // Aim teleports with the secondary laser. The floor stops the laser
+        // like the cubes do, so there's no teleporting through a cube.
+        let floor_reply = guru.secondary.pointing(None, &stage, &Plane::new(Vector3::y()), true, None);
+        let (output, debug_info) = if debug {
+            let (output, info) = guru.apply_with_debug(&mut self.guru_memory);
+            (output, Some(info))
+        } else {
+            (guru.apply(&mut self.guru_memory), None)
+        };
+        self.haptics.extend(output.haptics);
+        self.events.extend(output.events);
+        let cube_mats = cube_partials.into_iter().map(|mut p| p(self)).collect();
+
+        // Teleport: aim while the thumb is on the secondary pad, and go when
+        // it's lifted, unless the pad was used on a cube instead. Swiping
+        // calls the teleport off too.
+        let floor_hit = resolved(&floor_reply, "floor reply was not applied", &self.observer);
+        let touched = self.pads[Hand::Secondary.index()].touched();
+        let swiped = self.pads[Hand::Secondary.index()].swipe_y().is_some();
+        let busy = swiped || model.cubes.iter().any(|c| {
+            c.pointed[Hand::Secondary.index()]
+            || c.grabbed.map_or(false, |grab| grab.holds(Hand::Secondary))
+        });
+        if let Some(to) = self.teleport_aim.update(touched, busy, floor_hit.map(|h| h.point)) {
+            self.teleport(to, &stage, model);
+        }
+
+        limit_pulses(&mut self.haptics);
+
+        FrameView {
+            stage: self.world_offset * tracked,
+            light: light,
+            cube_mats: cube_mats,
+            lasers: lasers,
+            debug_info: debug_info,
         }
     }
+}
 
-    pub fn new<F: Factory<R> + FactoryExt<R>>(factory: &mut F) -> Result<Self, Error> {
+impl<R: gfx::Resources> App<R> {
+    /// Build the cubes of the scene, where they first appear.
+    pub fn model(&self) -> Model {
+        self.session.load(&self.scene)
+    }
+
+    /// Get the controller vibrations requested by the last frame.
+    pub fn haptics(&self) -> &[HapticRequest] {
+        &self.session.haptics
+    }
+
+    /// Get what the controllers started or stopped doing last frame, e.g. to
+    /// play sounds.
+    pub fn events(&self) -> &[InteractionEvent] {
+        &self.session.events
+    }
+
+    /// Always show the debug overlay, instead of only while the secondary
+    /// menu button is held.
+    pub fn set_debug(&mut self, on: bool) {
+        self.debug_pinned = on;
+    }
+
+    /// Aim from above big held cubes instead of through them.
+    pub fn set_lift_pointer(&mut self, on: bool) {
+        self.session.lift_pointer = on;
+    }
+
+    /// Snap released cubes to a grid, given relative to the stage, instead of
+    /// throwing them. `None` turns snapping off.
+    pub fn set_snap_grid(&mut self, grid: Option<SnapGrid>) {
+        self.session.snap_grid = grid;
+    }
+
+    /// Set the observer that is notified of usage events.
+    pub fn set_observer(&mut self, observer: Box<WorkbenchObserver>) {
+        self.session.observer.set(observer);
+    }
+
+    pub fn new<F: Factory<R> + FactoryExt<R>>(factory: &mut F) -> Result<Self, WorkbenchError> {
         // Setup Painters
-        let mut solid = Painter::new(factory)?;
-        solid.setup(factory, Primitive::LineList)?;
-        solid.setup(factory, Primitive::TriangleList)?;
+        let mut solid = Painter::new(factory).context("creating the solid painter")?;
+        solid.setup(factory, Primitive::LineList).context("setting up solid lines")?;
+        solid.setup(factory, Primitive::TriangleList).context("setting up solid triangles")?;
 
-        let mut pbr: Painter<_, PbrStyle<_>> = Painter::new(factory)?;
-        pbr.setup(factory, Primitive::TriangleList)?;
+        let mut pbr: Painter<_, PbrStyle<_>> = Painter::new(factory).context("creating the PBR painter")?;
+        pbr.setup(factory, Primitive::TriangleList).context("setting up PBR triangles")?;
 
-        let mat = AppMats::new(factory)?;
+        let mat = AppMats::new(factory).context("loading materials")?;
 
         // Construct App
         Ok(App {
             solid: solid,
             pbr: pbr,
-            controller: load::wavefront_file("assets/controller.obj")?
+            controller: load::wavefront_file(CONTROLLER_MESH)
+                .map_err(|e| WorkbenchError::asset_parse(CONTROLLER_MESH, e))?
                 .compute_tan()
-                .with_material(mat.plastic.clone())
+                .with_material(mat.get(PLASTIC).clone())
                 .upload(factory),
             line: MeshSource {
                     verts: vec![
@@ -188,149 +1145,607 @@ impl<R: gfx::Resources> App<R> {
                     prim: Primitive::LineList,
                     mat: (),
                 }.upload(factory),
+            dim_line: MeshSource {
+                    verts: vec![
+                        VertC { pos: [0., 0., 0.], color: [0.1, 0.33, 0.42] },
+                        VertC { pos: [0., 0., -1.], color: [0.2, 0.28, 0.31] },
+                    ],
+                    inds: Indexing::All,
+                    prim: Primitive::LineList,
+                    mat: (),
+                }.upload(factory),
+            dot: octahedron(1., [0.6, 0.9, 1.0]).upload(factory),
             cube: bevel_cube(1., 0.05)
                 .with_tex(Point2::new(0., 0.))
                 .compute_tan()
-                .with_material(mat.dark_plastic.clone())
+                .with_material(mat.get(DARK_PLASTIC).clone())
                 .upload(factory),
-            floor: plane(5.)
+            floor: plane(FLOOR_RADIUS)
                 .with_tex(Point2::new(0., 0.))
                 .compute_tan()
-                .with_material(mat.floor.clone())
+                .with_material(mat.get(FLOOR).clone())
                 .upload(factory),
             mats: mat,
-            primary: ViveController {
-                is: primary(),
-                .. Default::default()
-            },
-            secondary: ViveController {
-                is: secondary(),
-                .. Default::default()
-            }
+            session: Session::new(),
+            debug: DebugHud::new(factory),
+            debug_pinned: false,
+            scene: Scene::circle(),
         })
     }
 
+    /// Start with the scene described by a RON file, instead of the default
+    /// circle of cubes.
+    #[cfg(feature = "scene")]
+    pub fn from_scene<F: Factory<R> + FactoryExt<R>, P: AsRef<::std::path::Path>>(
+        factory: &mut F,
+        path: P,
+    ) -> Result<Self, WorkbenchError> {
+        let scene = Scene::load(path)?;
+        let mut app = App::new(factory)?;
+        app.scene = scene;
+        Ok(app)
+    }
+
     pub fn draw<C: gfx::CommandBuffer<R>>(
         &mut self,
         ctx: &mut DrawParams<R, C>,
         vrm: &VrMoment,
         model: &mut Model,
     ) {
-        match (self.primary.update(vrm), self.secondary.update(vrm)) {
-            (Ok(_), Ok(_)) => (),
-            _ => warn!("A not vive-like controller is connected"),
-        }
-        
+        let tracking = match (self.session.primary.update(vrm), self.session.secondary.update(vrm)) {
+            (Ok(_), Ok(_)) => true,
+            _ => false,
+        };
+        let tracked: Isometry3<f32> = na::try_convert(vrm.stage).unwrap_or(na::one());
+        self.frame(ctx, tracked, tracking, model);
+    }
+
+    /// Draw a frame with simulated controllers in place of tracked ones, e.g.
+    /// to work without a headset. `tracked` is the pose of the floor.
+    pub fn draw_with_inputs<C: gfx::CommandBuffer<R>>(
+        &mut self,
+        ctx: &mut DrawParams<R, C>,
+        tracked: &Isometry3<f32>,
+        primary: &SimController,
+        secondary: &SimController,
+        model: &mut Model,
+    ) {
+        primary.apply_to(&mut self.session.primary);
+        secondary.apply_to(&mut self.session.secondary);
+        self.frame(ctx, *tracked, true, model);
+    }
+
+    /// Update and draw everything, once the controllers are up to date.
+    /// `tracking` is false if they couldn't be updated.
+    fn frame<C: gfx::CommandBuffer<R>>(
+        &mut self,
+        ctx: &mut DrawParams<R, C>,
+        tracked: Isometry3<f32>,
+        tracking: bool,
+        model: &mut Model,
+    ) {
+        self.session.timing.tick();
+        let view = self.session.update(tracked, tracking, self.debug_pinned, model);
+        let stage = view.stage;
+        let light = view.light;
+        let background = [BACKGROUND[0] * light, BACKGROUND[1] * light, BACKGROUND[2] * light, BACKGROUND[3]];
+
         // Clear targets
         ctx.encoder.clear_depth(&ctx.depth, FAR_PLANE as f32);
-        ctx.encoder.clear(&ctx.color, [BACKGROUND[0].powf(1. / 2.2), BACKGROUND[1].powf(1. / 2.2), BACKGROUND[2].powf(1. / 2.2), BACKGROUND[3]]);
+        ctx.encoder.clear(&ctx.color, [background[0].powf(1. / 2.2), background[1].powf(1. / 2.2), background[2].powf(1. / 2.2), background[3]]);
 
         // Config PBR lights
         self.pbr.cfg(|s| {
-            s.ambient(BACKGROUND);
+            s.ambient(background);
             s.lights(&[
                 Light {
-                    pos: vrm.stage * Point3::new((0. * PI2 / 3.).sin() * 2., 4., (0. * PI2 / 3.).cos() * 2.),
-                    color: [1.0, 0.8, 0.8, 85.],
+                    pos: stage * Point3::new((0. * PI2 / 3.).sin() * 2., 4., (0. * PI2 / 3.).cos() * 2.),
+                    color: [1.0, 0.8, 0.8, 85. * light],
                 },
                 Light {
-                    pos: vrm.stage * Point3::new((1. * PI2 / 3.).sin() * 2., 4., (1. * PI2 / 3.).cos() * 2.),
-                    color: [0.8, 1.0, 0.8, 85.],
+                    pos: stage * Point3::new((1. * PI2 / 3.).sin() * 2., 4., (1. * PI2 / 3.).cos() * 2.),
+                    color: [0.8, 1.0, 0.8, 85. * light],
                 },
                 Light {
-                    pos: vrm.stage * Point3::new((2. * PI2 / 3.).sin() * 2., 4., (2. * PI2 / 3.).cos() * 2.),
-                    color: [0.8, 0.8, 1.0, 85.],
+                    pos: stage * Point3::new((2. * PI2 / 3.).sin() * 2., 4., (2. * PI2 / 3.).cos() * 2.),
+                    color: [0.8, 0.8, 1.0, 85. * light],
                 },
                 Light {
-                    pos: vrm.stage * Point3::new(0., -8., 0.),
-                    color: [1.0, 1.0, 1.0, 200.],
+                    pos: stage * Point3::new(0., -8., 0.),
+                    color: [1.0, 1.0, 1.0, 200. * light],
                 },
             ]);
         });
 
-        // Draw & update cubes
-        let mut guru = VrGuru::new(&self.primary, &self.secondary); 
-        let cube_partials: Vec<_> = model.cubes
-            .iter_mut()
-            .map(|model| {
-                if model.grabbed.is_some() && guru.primary.data.trigger > 0.5 {
-                    guru.primary.block_pointing();
-                } else {
-                    model.grabbed = None;
-                }
-                let cuboid = Cuboid3::new(Vector3::from_element(model.radius));
-                guru.primary.laser(&model.pos, &cuboid);
-                let reply = guru.primary.pointing(
-                    &model.pos,
-                    &cuboid,
-                    true);
-                move |ctx: &mut DrawParams<R, C>, app: &mut App<R>| {
-                    if let Some(_) = reply.expect("pointing not applied") {
-                        // TODO: speed not delta
-                        // Yank
-                        if app.primary.pad_delta[1] < 0. && app.primary.pad[1] < 0. {
-                            model.pos = Isometry3::from_parts(
-                                Translation3::from_vector((app.primary.pose() *
-                                Point3::new(0., 0., -0.1 -
-                                model.radius)).coords), model.pos.rotation,
-                            );
-                        }
+        // Draw cubes
+        for (cube, mat) in model.cubes.iter().zip(view.cube_mats) {
+            let mat = match mat {
+                Some(mat) => mat,
+                None => continue,
+            };
+            draw_with_mat(
+                &mut self.pbr,
+                ctx,
+                cube.draw_pose(),
+                &mut self.cube,
+                self.mats.get_mut(mat),
+            );
+            if view.debug_info.is_some() {
+                let state = match mat {
+                    BLUE_PLASTIC => CubeState::Grabbed,
+                    HOVER_PLASTIC => CubeState::Hovered,
+                    _ => CubeState::Sitting,
+                };
+                self.debug.draw_cube(&mut self.solid, ctx, cube.draw_pose(), state);
+            }
+        }
 
-                        // TODO: speed not delta
-                        // Push
-                        if app.primary.pad_delta[1] > 0. && app.primary.pad[1] > 0. {
-                            model.pos = Isometry3::from_parts(
-                                Translation3::from_vector((app.primary.pose() *
-                                Point3::new(0., 0., -2.5)).coords),
-                                model.pos.rotation,
-                            );
-                        }
+        if let Some(to) = self.session.teleport_aim.target {
+            // Mark the target, just above the floor so it doesn't flicker
+            let up = stage.rotation * Vector3::y();
+            let marker = Isometry3::from_parts(
+                Translation3::from_vector(to.coords + up * 0.005),
+                stage.rotation,
+            );
+            draw_with_mat(
+                &mut self.pbr,
+                ctx,
+                Similarity3::from_isometry(marker, 0.04),
+                &mut self.floor,
+                self.mats.get_mut(BLUE_PLASTIC),
+            );
+        }
 
-                        // Grab
-                        if app.primary.trigger > 0.5 
-                        && app.primary.trigger - app.primary.trigger_delta < 0.5 {
-                            model.grabbed = Some(app.primary.pose().inverse() * model.pos);
-                        }
-                    }
-                    // Update position
-                    if let Some(off) = model.grabbed {
-                        model.pos = app.primary.pose() * off;
-                        app.pbr.draw(
-                            ctx,
-                            na::convert(Similarity3::from_isometry(model.pos, model.radius)),
-                            &Mesh {
-                                mat: app.mats.blue_plastic.clone(),
-                                .. app.cube.clone()
-                            },
-                        );
-                    } else {
-                        app.pbr.draw(
-                            ctx,
-                            na::convert(Similarity3::from_isometry(model.pos, model.radius)),
-                            &app.cube
-                        );
-                    }
-                }
-            })
-            .collect();
-        let stage = na::try_convert(vrm.stage).unwrap_or(na::one());
-        guru.primary.laser(&stage, &Plane::new(Vector3::y()));
-        let toi = guru.primary.laser_toi.unwrap_or(FAR_PLANE as f32).max(0.01);
-        guru.apply();
-        for mut p in cube_partials {
-            p(ctx, self);
+        // Draw the debug overlay
+        if let Some(ref info) = view.debug_info {
+            self.debug.draw_guru(&mut self.solid, ctx, info);
+            for &h in HANDS.iter() {
+                let (pose, trigger, pad) = {
+                    let c = self.session.controller(h);
+                    (c.pose(), c.trigger, [c.pad[0], c.pad[1]])
+                };
+                self.debug.draw_controls(&mut self.solid, ctx, &pose, trigger, pad);
+            }
         }
 
         // Draw controllers
-        for cont in vrm.controllers() {
-            self.pbr.draw(ctx, na::convert(cont.pose), &self.controller);
+        if tracking {
+            for &h in HANDS.iter() {
+                self.pbr.draw(ctx, na::convert(self.session.controller(h).pose()), &self.controller);
+            }
         }
 
-        self.solid.draw(ctx, na::convert(
-            Similarity3::from_isometry(self.primary.pose(), toi)
-        ), &self.line);
+        // Draw lasers: bright with a dot when on a cube, dim when on the
+        // floor, and a short stub when on nothing
+        for &(h, origin, toi, id, laser_start) in &view.lasers {
+            let pose = Isometry3::from_parts(
+                Translation3::from_vector(origin.coords),
+                self.session.controller(h).pose().rotation,
+            );
+            let (end, line) = match (toi, id) {
+                (Some(toi), Some(_)) => {
+                    let dot = pose * Point3::new(0., 0., -toi);
+                    self.solid.draw(ctx, na::convert(Similarity3::from_parts(
+                        Translation3::from_vector(dot.coords),
+                        UnitQuaternion::identity(),
+                        LASER_DOT,
+                    )), &self.dot);
+                    (toi.max(0.01), &self.line)
+                },
+                (Some(toi), None) => (toi.max(0.01), &self.dim_line),
+                (None, _) => (laser_start + LASER_STUB, &self.dim_line),
+            };
+            let laser_start = laser_start.min(end);
+            let laser_pose = pose * Isometry3::from_parts(
+                Translation3::new(0., 0., -laser_start),
+                UnitQuaternion::identity(),
+            );
+            self.solid.draw(ctx, na::convert(
+                Similarity3::from_isometry(laser_pose, (end - laser_start).max(0.01))
+            ), line);
+        }
 
-        // Draw floor
+        // Draw floor, and the planes of the scene with the same mesh
         self.pbr.draw(ctx, na::convert(stage), &self.floor);
+        for plane in &self.scene.planes {
+            draw_with_mat(
+                &mut self.pbr,
+                ctx,
+                Similarity3::from_isometry(self.session.world_offset * plane.pose, plane.radius / FLOOR_RADIUS),
+                &mut self.floor,
+                self.mats.get_mut(plane.mat),
+            );
+        }
+
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::f32::consts::FRAC_PI_4;
+    use std::rc::Rc;
+
+    /// Remembers every notification, in order.
+    struct Recorder(Rc<RefCell<Vec<String>>>);
+
+    impl WorkbenchObserver for Recorder {
+        fn session_started(&mut self) {
+            self.0.borrow_mut().push("started".to_string());
+        }
+
+        fn scene_loaded(&mut self, object_count: usize) {
+            self.0.borrow_mut().push(format!("loaded {}", object_count));
+        }
+
+        fn grab_completed(&mut self, _: f32, distance: f32) {
+            self.0.borrow_mut().push(format!("grabbed {:.2}", distance));
+        }
+
+        fn teleport(&mut self, distance: f32) {
+            self.0.borrow_mut().push(format!("teleported {:.2}", distance));
+        }
+
+        fn object_spawned(&mut self, kind: &str) {
+            self.0.borrow_mut().push(format!("spawned {}", kind));
+        }
+
+        fn error(&mut self, category: &str, message: &str) {
+            self.0.borrow_mut().push(format!("error {} {}", category, message));
+        }
+
+        fn frame_budget_exceeded(&mut self, class: &str) {
+            self.0.borrow_mut().push(format!("over budget {}", class));
+        }
+    }
+
+    fn recording() -> (ObserverSlot, Rc<RefCell<Vec<String>>>) {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        (ObserverSlot::with(Box::new(Recorder(log.clone()))), log)
+    }
+
+    #[test]
+    fn unapplied_reply_is_a_reported_miss() {
+        let (observer, log) = recording();
+        let reply: Anywhere<Option<u32>> = Anywhere::new();
+        assert_eq!(resolved(&reply, "pointing reply was not applied", &observer), None);
+        assert_eq!(*log.borrow(), vec!["error internal pointing reply was not applied".to_string()]);
+
+        reply.put(Some(3));
+        assert_eq!(resolved(&reply, "pointing reply was not applied", &observer), Some(3));
+        assert_eq!(log.borrow().len(), 1);
+    }
+
+    /// Run a frame of the model the way `App::frame` does.
+    fn run_frame(timing: &mut FrameTiming, model: &mut Model, observer: &ObserverSlot, dt: f32) {
+        timing.advance(dt);
+        check_budget(timing, observer);
+        model.update(timing.delta, &na::one(), observer);
+    }
+
+    #[test]
+    fn session_notifies_in_order() {
+        let (observer, log) = recording();
+        let mut timing = FrameTiming::new();
+        let mut model = Model::new(&Scene::circle());
+        run_frame(&mut timing, &mut model, &observer, 0.);
+
+        // Knock a cube out of the world, then hitch while it's gone
+        model.cubes[3].pos.translation.vector.y = -50.;
+        run_frame(&mut timing, &mut model, &observer, 1. / 90.);
+        assert!(model.cubes[3].dead.is_some());
+        run_frame(&mut timing, &mut model, &observer, 0.03);
+        run_frame(&mut timing, &mut model, &observer, 0.2);
+        for _ in 0..90 {
+            run_frame(&mut timing, &mut model, &observer, 1. / 90.);
+        }
+        assert!(model.cubes[3].dead.is_none());
+        assert_eq!(*log.borrow(), vec![
+            "over budget missed".to_string(),
+            "over budget stall".to_string(),
+            "spawned cube".to_string(),
+        ]);
+    }
+
+    /// Step a session by `dt` with simulated controllers, the way
+    /// `App::draw_with_inputs` does.
+    fn sim_frame(session: &mut Session, sims: &mut [SimController; 2], model: &mut Model, dt: f32) {
+        for sim in sims.iter_mut() {
+            sim.update(dt);
+        }
+        sims[0].apply_to(&mut session.primary);
+        sims[1].apply_to(&mut session.secondary);
+        session.timing.advance(dt);
+        session.update(na::one(), true, false, model);
+    }
+
+    #[test]
+    fn scripted_session_notifies_in_order() {
+        let (observer, log) = recording();
+        let mut session = Session::new();
+        session.observer = observer;
+        let level = |x: f32, y: f32, z: f32| Isometry3::new(Vector3::new(x, y, z), na::zero());
+        let mut sims = [SimController::at(level(0., 0.1, 0.5)), SimController::at(level(1.5, 1., 0.))];
+        let dt = 1. / 90.;
+
+        let mut model = Model::new(&Scene { cubes: Vec::new(), planes: Vec::new() });
+        sim_frame(&mut session, &mut sims, &mut model, 0.);
+        model = session.load(&one_cube_scene(Vector3::new(0., 0.1, -1.), 0.1));
+        for _ in 0..90 {
+            sim_frame(&mut session, &mut sims, &mut model, dt);
+        }
+
+        // Grab the cube with the laser, lift it, and let go
+        sims[0].press(true);
+        sim_frame(&mut session, &mut sims, &mut model, dt);
+        assert!(model.cubes[0].grabbed.is_some());
+        for i in 1..11 {
+            sims[0].pose = level(0., 0.1 + 0.05 * i as f32, 0.5);
+            sim_frame(&mut session, &mut sims, &mut model, dt);
+        }
+        sims[0].press(false);
+        sim_frame(&mut session, &mut sims, &mut model, dt);
+        assert!(model.cubes[0].grabbed.is_none());
+
+        // Aim the other hand at the floor with a thumb on the pad, then lift
+        // the thumb
+        sims[1].pose = Isometry3::new(Vector3::new(1.5, 1., 0.), Vector3::x() * -FRAC_PI_4);
+        sims[1].scroll(1.);
+        for _ in 0..45 {
+            sim_frame(&mut session, &mut sims, &mut model, dt);
+        }
+
+        // Lose tracking for a few frames, then hitch
+        for _ in 0..3 {
+            session.timing.advance(dt);
+            session.update(na::one(), false, false, &mut model);
+        }
+        sim_frame(&mut session, &mut sims, &mut model, 0.03);
+        sim_frame(&mut session, &mut sims, &mut model, dt);
+
+        assert_eq!(*log.borrow(), vec![
+            "started".to_string(),
+            "loaded 1".to_string(),
+            "grabbed 0.50".to_string(),
+            "teleported 1.00".to_string(),
+            "error vr_runtime transient VR runtime error: a not vive-like controller is connected".to_string(),
+            "over budget missed".to_string(),
+        ]);
+    }
+
+    #[test]
+    fn lifted_origin_clears_turned_cubes() {
+        use ncollide::query::{Ray, RayCast};
+
+        let mut model = Model::new(&Scene::circle());
+        let cube = &mut model.cubes[0];
+        cube.radius = 0.4;
+        let shape = Cuboid3::new(Vector3::from_element(cube.radius));
+        for i in 0..20 {
+            let turn = i as f32 * 0.37;
+            cube.pos = Isometry3::new(Vector3::new(0., 1., -0.3), Vector3::new(turn, turn * 0.5, 0.));
+            let origin = lifted_origin(cube, &Vector3::y());
+            for &dir in &[-Vector3::z(), Vector3::x(), Vector3::new(0., -0.2, -1.).normalize()] {
+                let ray = Ray::new(origin, dir);
+                assert!(shape.toi_with_ray(&cube.pos, &ray, true).is_none(), "hit at turn {}", turn);
+            }
+        }
+    }
+
+    /// A scene of one cube of the given radius, turned a little, at `pos`.
+    fn one_cube_scene(pos: Vector3<f32>, radius: f32) -> Scene {
+        Scene {
+            cubes: vec![::scene::CubeSpawn {
+                pose: Isometry3::new(pos, Vector3::new(0.3, 0.5, 0.1)),
+                radius: radius,
+                mat: DARK_PLASTIC,
+                grabbable: true,
+            }],
+            planes: Vec::new(),
+        }
+    }
+
+    fn one_cube(pos: Vector3<f32>, radius: f32) -> Model {
+        Model::new(&one_cube_scene(pos, radius))
+    }
+
+    #[test]
+    fn fallen_cube_respawns_where_it_started() {
+        let (observer, log) = recording();
+        let spawn = Vector3::new(0.5, 1.5, -1.);
+        let mut model = one_cube(spawn, 0.2);
+        let start = model.cubes[0].pos;
+        {
+            let cube = &mut model.cubes[0];
+            // Asleep, so the floor doesn't catch it before it's removed
+            cube.pos = Isometry3::new(Vector3::new(3., -50., 2.), Vector3::new(1., 0., 0.));
+            cube.sleeping = true;
+            cube.radius = 0.5;
+        }
+        let dt = 1. / 90.;
+        model.update(dt, &na::one(), &observer);
+        assert!(model.cubes[0].dead.is_some());
+
+        // Gone for the whole delay, then back in one frame
+        let mut frames = 0;
+        while model.cubes[0].dead.is_some() {
+            model.update(dt, &na::one(), &observer);
+            frames += 1;
+            assert!(frames < 200, "never respawned");
+        }
+        assert!((frames as f32 * dt - RESPAWN_DELAY).abs() <= dt, "respawned after {} frames", frames);
+        assert_eq!(*log.borrow(), vec!["spawned cube".to_string()]);
+
+        // Exactly at the spawn pose, at rest and full size, growing in
+        let cube = &model.cubes[0];
+        assert_eq!(cube.pos, start);
+        assert_eq!(cube.vel, na::zero());
+        assert_eq!(cube.ang_vel, na::zero());
+        assert_eq!(cube.radius, 0.2);
+        assert!((cube.draw_pose().scaling() - 0.2 * SPAWN_SCALE).abs() < 1e-4,
+            "drawn at {}", cube.draw_pose().scaling());
+    }
+
+    #[test]
+    fn cubes_land_on_planes_they_fall_onto() {
+        let (observer, _) = recording();
+        let shelf = PlaneSpawn {
+            pose: Isometry3::new(Vector3::new(0., 0.5, -1.), Vector3::new(0., 0.4, 0.)),
+            radius: 0.6,
+            mat: DARK_PLASTIC,
+        };
+        let mut model = Model::new(&Scene {
+            cubes: vec![0., 0.8].iter().map(|&x| ::scene::CubeSpawn {
+                pose: Isometry3::new(Vector3::new(x, 1., -1.), Vector3::new(0.3, 0.5, 0.1)),
+                radius: 0.1,
+                mat: DARK_PLASTIC,
+                grabbable: true,
+            }).collect(),
+            planes: vec![shelf],
+        });
+        for _ in 0..(90 * 5) {
+            model.update(1. / 90., &na::one(), &observer);
+        }
+        // One on the shelf, one off its edge and down on the floor
+        let heights: Vec<f32> = model.cubes.iter().map(|c| c.pos.translation.vector.y).collect();
+        assert!((heights[0] - 0.6).abs() < 1e-3, "rests at {:?}", heights);
+        assert!((heights[1] - 0.1).abs() < 1e-3, "rests at {:?}", heights);
+        assert!(model.cubes.iter().all(|c| c.sleeping));
+
+        // The shelf stays put in the world through a teleport
+        model.shift(&Vector3::new(2., 0., 0.));
+        model.cubes[0].sleeping = false;
+        for _ in 0..90 {
+            model.update(1. / 90., &na::one(), &observer);
+        }
+        let p = model.cubes[0].pos.translation.vector;
+        assert!((p.x - 2.).abs() < 1e-3 && (p.y - 0.6).abs() < 1e-3, "moved to {:?}", p);
+    }
+
+    #[test]
+    fn dropped_cube_comes_to_rest_on_the_floor() {
+        let (observer, _) = recording();
+        let mut model = one_cube(Vector3::new(0.5, 1.5, -1.), 0.2);
+        let mut slept = false;
+        for _ in 0..(90 * 5) {
+            model.update(1. / 90., &na::one(), &observer);
+            let cube = &model.cubes[0];
+            // Never sinks into the floor on the way
+            assert!(cube.pos.translation.vector.y >= 0.2 - 1e-4, "sank to {}", cube.pos.translation.vector.y);
+            slept = slept || cube.sleeping;
+        }
+        let cube = &model.cubes[0];
+        assert!(slept && cube.sleeping);
+        assert!((cube.pos.translation.vector.y - 0.2).abs() < 1e-3, "rests at {}", cube.pos.translation.vector.y);
+        // Flat on a face
+        let up = cube.pos.rotation.inverse() * Vector3::y();
+        assert!(up.iter().any(|x| (x.abs() - 1.).abs() < 1e-3), "tilted {:?}", up);
+
+        // On a raised, tilted stage the floor moves with it
+        let stage = Isometry3::new(Vector3::new(0., 2., 0.), Vector3::new(0.2, 0., 0.));
+        let mut model = one_cube(stage.translation.vector + Vector3::new(0., 1., 0.), 0.1);
+        for _ in 0..(90 * 5) {
+            model.update(1. / 90., &stage, &observer);
+        }
+        let cube = &model.cubes[0];
+        let height = (cube.pos.translation.vector - stage.translation.vector).dot(&(stage.rotation * Vector3::y()));
+        assert!((height - 0.1).abs() < 1e-3, "rests at {}", height);
+    }
+
+    /// Swipe up the pad at `rate` pad lengths per second, running at `fps`,
+    /// and get where a cube first hit at `toi` ends up.
+    fn swipe_at(fps: f32, rate: f32, toi: f32) -> f32 {
+        let dt = 1. / fps;
+        let mut pad = PadFilter::new();
+        let mut toi = toi;
+        for i in 0..(fps * 0.6) as usize {
+            let y = (-0.4 + rate * i as f32 * dt).min(0.4);
+            pad.update([0.1, y], dt);
+            toi = yank_push(toi, pad.delta()[1], dt);
+        }
+        toi
+    }
+
+    #[test]
+    fn yank_and_push_ignore_the_frame_rate() {
+        for &rate in &[1., 2., 4.] {
+            let slow = swipe_at(45., rate, 1.);
+            let fast = swipe_at(90., rate, 1.);
+            let faster = swipe_at(144., rate, 1.);
+            assert!(slow > 1.1, "only pushed to {}", slow);
+            assert!((slow - fast).abs() < 0.02, "{} at 45fps, {} at 90fps", slow, fast);
+            assert!((faster - fast).abs() < 0.02, "{} at 144fps, {} at 90fps", faster, fast);
+        }
+        // Faster swipes over the same length go farther
+        assert!(swipe_at(90., 4., 1.) > swipe_at(90., 1., 1.) + 0.1);
+
+        // Limits hold cubes in reach, but leave cubes already past them
+        assert_eq!(yank_push(1., -10., 0.01), YANK_DISTANCE);
+        assert_eq!(yank_push(1., 10., 0.01), PUSH_DISTANCE);
+        assert_eq!(yank_push(3., 0.1, 0.01), 3.);
+        assert_eq!(yank_push(1., 0.1, 0.), 1.);
+    }
+
+    #[test]
+    fn teleport_waits_for_the_thumb_and_skips_cube_swipes() {
+        let (a, b) = (Point3::new(1., 0., 0.), Point3::new(2., 0., 0.));
+        let mut aim = TeleportAim::new();
+        assert_eq!(aim.update(true, false, Some(a)), None);
+        assert_eq!(aim.update(true, false, Some(b)), None);
+        assert_eq!(aim.target, Some(b));
+        // Goes where it was last aimed once the thumb is lifted, once
+        assert_eq!(aim.update(false, false, Some(a)), Some(b));
+        assert_eq!(aim.update(false, false, Some(a)), None);
+
+        // Swiping on a cube, then sweeping the laser onto the floor before
+        // lifting the thumb, doesn't teleport
+        assert_eq!(aim.update(true, true, None), None);
+        assert_eq!(aim.update(true, false, Some(a)), None);
+        assert_eq!(aim.target, None);
+        assert_eq!(aim.update(false, false, Some(a)), None);
+        // But the next touch aims again
+        aim.update(true, false, Some(a));
+        assert_eq!(aim.update(false, false, None), Some(a));
+
+        // Aiming off the floor cancels too
+        aim.update(true, false, Some(a));
+        aim.update(true, false, None);
+        assert_eq!(aim.update(false, false, None), None);
+    }
+
+    #[test]
+    fn snapped_cube_stays_on_the_grid() {
+        let (observer, _) = recording();
+        let mut model = one_cube(Vector3::new(0.33, 1.2, -0.58), 0.1);
+        let grid = SnapGrid::new(na::one(), 0.25, 4);
+        let to = grid.snap_isometry(&model.cubes[0].pos);
+        {
+            let cube = &mut model.cubes[0];
+            cube.snap = Some(Animate::Eased(cube.pos, to, EasingKind::QuadOut, 0., SNAP_TIME));
+        }
+        for _ in 0..90 {
+            model.update(1. / 90., &na::one(), &observer);
+        }
+        let cube = &model.cubes[0];
+        assert!(cube.snap.is_none() && cube.sleeping);
+        // Still in the air, where the grid put it
+        assert_eq!(cube.pos, to);
+        assert!((cube.pos.translation.vector - Vector3::new(0.25, 1.25, -0.5)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn materials_are_lent_not_cloned() {
+        // Can't be cloned, so lending it can't clone it either
+        struct Mat(u32);
+        struct Mesh { mat: Mat, verts: usize }
+
+        let mut mesh = Mesh { mat: Mat(1), verts: 24 };
+        let mut lent = Mat(2);
+        let mut seen = None;
+        lend_mat(&mut mesh, |m| &mut m.mat, &mut lent, |m| seen = Some((m.mat.0, m.verts)));
+        assert_eq!(seen, Some((2, 24)));
+        assert_eq!(mesh.mat.0, 1);
+        assert_eq!(lent.0, 2);
     }
 }
@@ -0,0 +1,207 @@
+//! Overlays showing what the interaction code is doing, drawn with lines so
+//! that no text rendering is needed.
+
+use gfx::{self, Factory};
+use gfx::traits::FactoryExt;
+use nalgebra::{self as na, Isometry3, Point3, Similarity3, Translation3, UnitQuaternion, Vector3};
+use flight::mesh::*;
+use flight::draw::{DrawParams, Painter, SolidStyle};
+
+use interact::{ControllerDebug, GuruDebugInfo};
+
+/// The size of the crosses marking query hits.
+const MARKER_SIZE: f32 = 0.015;
+/// The length of the trigger bar.
+const BAR_LENGTH: f32 = 0.08;
+/// Half the width of the pad indicator.
+const PAD_SIZE: f32 = 0.03;
+
+const GRAY: [f32; 3] = [0.4, 0.4, 0.4];
+const WHITE: [f32; 3] = [0.9, 0.9, 0.9];
+const GREEN: [f32; 3] = [0.2, 0.9, 0.2];
+const RED: [f32; 3] = [0.9, 0.2, 0.2];
+const CYAN: [f32; 3] = [0.2, 0.8, 0.9];
+const YELLOW: [f32; 3] = [0.9, 0.8, 0.2];
+const ORANGE: [f32; 3] = [0.9, 0.5, 0.1];
+
+/// What a cube is doing, for color coding.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum CubeState {
+    Sitting,
+    Hovered,
+    Grabbed,
+}
+
+/// Meshes for drawing debug overlays with the solid painter.
+pub struct DebugHud<R: gfx::Resources> {
+    /// A unit line along `-z`, for rays.
+    ray: Mesh<R, VertC, ()>,
+    /// Unit crosses marking answered hits, hidden hits and touches.
+    hit: Mesh<R, VertC, ()>,
+    missed: Mesh<R, VertC, ()>,
+    touch: Mesh<R, VertC, ()>,
+    /// Unit lines along `+x`, for bars.
+    bar_track: Mesh<R, VertC, ()>,
+    bar_fill: Mesh<R, VertC, ()>,
+    /// A square from `-1` to `1` in the `xy` plane, framing the pad.
+    square: Mesh<R, VertC, ()>,
+    /// Wireframe cubes from `-1` to `1`, one for each `CubeState`.
+    sitting: Mesh<R, VertC, ()>,
+    hovered: Mesh<R, VertC, ()>,
+    grabbed: Mesh<R, VertC, ()>,
+}
+
+/// A line list mesh of the given segments, all one color.
+fn lines(segments: &[([f32; 3], [f32; 3])], color: [f32; 3]) -> MeshSource<VertC, ()> {
+    MeshSource {
+        verts: segments.iter()
+            .flat_map(|&(a, b)| vec![
+                VertC { pos: a, color: color },
+                VertC { pos: b, color: color },
+            ])
+            .collect(),
+        inds: Indexing::All,
+        prim: Primitive::LineList,
+        mat: (),
+    }
+}
+
+fn cross(color: [f32; 3]) -> MeshSource<VertC, ()> {
+    lines(&[
+        ([-1., 0., 0.], [1., 0., 0.]),
+        ([0., -1., 0.], [0., 1., 0.]),
+        ([0., 0., -1.], [0., 0., 1.]),
+    ], color)
+}
+
+fn wire_cube(color: [f32; 3]) -> MeshSource<VertC, ()> {
+    let mut segments = Vec::with_capacity(12);
+    for &a in &[-1., 1.] {
+        for &b in &[-1., 1.] {
+            segments.push(([-1., a, b], [1., a, b]));
+            segments.push(([a, -1., b], [a, 1., b]));
+            segments.push(([a, b, -1.], [a, b, 1.]));
+        }
+    }
+    lines(&segments, color)
+}
+
+impl<R: gfx::Resources> DebugHud<R> {
+    pub fn new<F: Factory<R> + FactoryExt<R>>(f: &mut F) -> DebugHud<R> {
+        DebugHud {
+            ray: lines(&[([0., 0., 0.], [0., 0., -1.])], WHITE).upload(f),
+            hit: cross(GREEN).upload(f),
+            missed: cross(RED).upload(f),
+            touch: cross(CYAN).upload(f),
+            bar_track: lines(&[([0., 0., 0.], [1., 0., 0.])], GRAY).upload(f),
+            bar_fill: lines(&[([0., 0., 0.], [1., 0., 0.])], ORANGE).upload(f),
+            square: lines(&[
+                ([-1., -1., 0.], [1., -1., 0.]),
+                ([1., -1., 0.], [1., 1., 0.]),
+                ([1., 1., 0.], [-1., 1., 0.]),
+                ([-1., 1., 0.], [-1., -1., 0.]),
+            ], GRAY).upload(f),
+            sitting: wire_cube(GRAY).upload(f),
+            hovered: wire_cube(YELLOW).upload(f),
+            grabbed: wire_cube(GREEN).upload(f),
+        }
+    }
+
+    /// Draw each controller's ray out to where the laser stops, with a
+    /// cross at every pointing query hit and at the center of every touched shape.
+    /// A controller that can't touch anything gets a red cross at its origin.
+    pub fn draw_guru<C: gfx::CommandBuffer<R>>(
+        &self,
+        painter: &mut Painter<R, SolidStyle<R>>,
+        ctx: &mut DrawParams<R, C>,
+        info: &GuruDebugInfo,
+    ) {
+        for debug in &[&info.primary, &info.secondary] {
+            self.draw_controller(painter, ctx, debug);
+        }
+    }
+
+    fn draw_controller<C: gfx::CommandBuffer<R>>(
+        &self,
+        painter: &mut Painter<R, SolidStyle<R>>,
+        ctx: &mut DrawParams<R, C>,
+        debug: &ControllerDebug,
+    ) {
+        let rot = UnitQuaternion::rotation_between(&-Vector3::z(), &debug.pointing)
+            .unwrap_or(UnitQuaternion::identity());
+        let ray = Isometry3::from_parts(Translation3::from_vector(debug.origin.coords), rot);
+        if !debug.pointing_blocked {
+            let len = debug.laser_toi.unwrap_or(1.).max(0.01);
+            painter.draw(ctx, na::convert(Similarity3::from_isometry(ray, len)), &self.ray);
+        }
+        for &(at, _, answered) in &debug.pointing_hits {
+            let mesh = if answered { &self.hit } else { &self.missed };
+            self.marker(painter, ctx, at, mesh);
+        }
+        for &(at, _, answered) in &debug.touches {
+            let mesh = if answered { &self.touch } else { &self.missed };
+            self.marker(painter, ctx, at, mesh);
+        }
+        if debug.touching_blocked {
+            self.marker(painter, ctx, debug.origin, &self.missed);
+        }
+    }
+
+    fn marker<C: gfx::CommandBuffer<R>>(
+        &self,
+        painter: &mut Painter<R, SolidStyle<R>>,
+        ctx: &mut DrawParams<R, C>,
+        at: Point3<f32>,
+        mesh: &Mesh<R, VertC, ()>,
+    ) {
+        painter.draw(ctx, na::convert(Similarity3::from_parts(
+            Translation3::from_vector(at.coords),
+            UnitQuaternion::identity(),
+            MARKER_SIZE,
+        )), mesh);
+    }
+
+    /// Draw a bar for the trigger value and a frame with a cross for the pad
+    /// position, just above a controller.
+    pub fn draw_controls<C: gfx::CommandBuffer<R>>(
+        &self,
+        painter: &mut Painter<R, SolidStyle<R>>,
+        ctx: &mut DrawParams<R, C>,
+        pose: &Isometry3<f32>,
+        trigger: f32,
+        pad: [f32; 2],
+    ) {
+        let at = |x: f32, y: f32, z: f32, scale: f32| na::convert(Similarity3::from_isometry(
+            pose * Isometry3::from_parts(Translation3::new(x, y, z), UnitQuaternion::identity()),
+            scale,
+        ));
+        let left = -BAR_LENGTH / 2.;
+        painter.draw(ctx, at(left, 0.06, 0., BAR_LENGTH), &self.bar_track);
+        if trigger > 0. {
+            painter.draw(ctx, at(left, 0.065, 0., BAR_LENGTH * trigger.min(1.)), &self.bar_fill);
+        }
+        painter.draw(ctx, at(0., 0.11, 0., PAD_SIZE), &self.square);
+        if pad[0] != 0. || pad[1] != 0. {
+            painter.draw(ctx, at(pad[0] * PAD_SIZE, 0.11 + pad[1] * PAD_SIZE, 0., MARKER_SIZE / 2.), &self.touch);
+        }
+    }
+
+    /// Draw a color coded box around a cube, given its pose scaled to its
+    /// radius.
+    pub fn draw_cube<C: gfx::CommandBuffer<R>>(
+        &self,
+        painter: &mut Painter<R, SolidStyle<R>>,
+        ctx: &mut DrawParams<R, C>,
+        pose: Similarity3<f32>,
+        state: CubeState,
+    ) {
+        let mesh = match state {
+            CubeState::Sitting => &self.sitting,
+            CubeState::Hovered => &self.hovered,
+            CubeState::Grabbed => &self.grabbed,
+        };
+        // A little bigger, so the box doesn't hide inside the cube
+        let pose = Similarity3::from_isometry(pose.isometry, pose.scaling() * 1.02);
+        painter.draw(ctx, na::convert(pose), mesh);
+    }
+}
@@ -0,0 +1,179 @@
+//! Standard easing curves. Each maps `0→0` and `1→1`, although some
+//! overshoot in between.
+
+use animation::Param;
+#[cfg(feature = "serde")]
+use serde::{Serialize, Deserialize};
+
+const PI: f32 = ::std::f32::consts::PI;
+
+/// Names one of the easing curves in this module.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+// The in-out curves are only ever picked by scene files
+#[cfg_attr(not(feature = "serde"), allow(dead_code))]
+pub enum EasingKind {
+    QuadIn,
+    QuadOut,
+    QuadInOut,
+    CubicIn,
+    CubicOut,
+    CubicInOut,
+    ElasticIn,
+    ElasticOut,
+    ElasticInOut,
+    BackIn,
+    BackOut,
+    BackInOut,
+    BounceIn,
+    BounceOut,
+    BounceInOut,
+}
+
+impl EasingKind {
+    /// Evaluate the curve at `t`.
+    pub fn apply(self, t: Param) -> Param {
+        use self::EasingKind::*;
+        match self {
+            QuadIn => quad_in(t),
+            QuadOut => quad_out(t),
+            QuadInOut => quad_in_out(t),
+            CubicIn => cubic_in(t),
+            CubicOut => cubic_out(t),
+            CubicInOut => cubic_in_out(t),
+            ElasticIn => elastic_in(t),
+            ElasticOut => elastic_out(t),
+            ElasticInOut => elastic_in_out(t),
+            BackIn => back_in(t),
+            BackOut => back_out(t),
+            BackInOut => back_in_out(t),
+            BounceIn => bounce_in(t),
+            BounceOut => bounce_out(t),
+            BounceInOut => bounce_in_out(t),
+        }
+    }
+
+    /// Get the curve `m` such that `m(t) = 1 - self(1 - t)`, which is the
+    /// curve played backwards.
+    pub fn mirror(self) -> EasingKind {
+        use self::EasingKind::*;
+        match self {
+            QuadIn => QuadOut,
+            QuadOut => QuadIn,
+            CubicIn => CubicOut,
+            CubicOut => CubicIn,
+            ElasticIn => ElasticOut,
+            ElasticOut => ElasticIn,
+            BackIn => BackOut,
+            BackOut => BackIn,
+            BounceIn => BounceOut,
+            BounceOut => BounceIn,
+            symmetric => symmetric,
+        }
+    }
+}
+
+pub fn quad_in(t: Param) -> Param {
+    t * t
+}
+
+pub fn quad_out(t: Param) -> Param {
+    t * (2. - t)
+}
+
+pub fn quad_in_out(t: Param) -> Param {
+    if t < 0.5 {
+        2. * t * t
+    } else {
+        -1. + (4. - 2. * t) * t
+    }
+}
+
+pub fn cubic_in(t: Param) -> Param {
+    t * t * t
+}
+
+pub fn cubic_out(t: Param) -> Param {
+    let x = t - 1.;
+    x * x * x + 1.
+}
+
+pub fn cubic_in_out(t: Param) -> Param {
+    if t < 0.5 {
+        4. * t * t * t
+    } else {
+        let x = 2. * t - 2.;
+        0.5 * x * x * x + 1.
+    }
+}
+
+pub fn elastic_in(t: Param) -> Param {
+    if t <= 0. || t >= 1. { return t.max(0.).min(1.) }
+    -(2f32).powf(10. * t - 10.) * ((10. * t - 10.75) * (2. * PI / 3.)).sin()
+}
+
+pub fn elastic_out(t: Param) -> Param {
+    if t <= 0. || t >= 1. { return t.max(0.).min(1.) }
+    (2f32).powf(-10. * t) * ((10. * t - 0.75) * (2. * PI / 3.)).sin() + 1.
+}
+
+pub fn elastic_in_out(t: Param) -> Param {
+    if t <= 0. || t >= 1. { return t.max(0.).min(1.) }
+    let c = 2. * PI / 4.5;
+    if t < 0.5 {
+        -0.5 * (2f32).powf(20. * t - 10.) * ((20. * t - 11.125) * c).sin()
+    } else {
+        0.5 * (2f32).powf(-20. * t + 10.) * ((20. * t - 11.125) * c).sin() + 1.
+    }
+}
+
+const BACK: f32 = 1.70158;
+
+pub fn back_in(t: Param) -> Param {
+    t * t * ((BACK + 1.) * t - BACK)
+}
+
+pub fn back_out(t: Param) -> Param {
+    let x = t - 1.;
+    x * x * ((BACK + 1.) * x + BACK) + 1.
+}
+
+pub fn back_in_out(t: Param) -> Param {
+    let c = BACK * 1.525;
+    if t < 0.5 {
+        let x = 2. * t;
+        0.5 * x * x * ((c + 1.) * x - c)
+    } else {
+        let x = 2. * t - 2.;
+        0.5 * (x * x * ((c + 1.) * x + c) + 2.)
+    }
+}
+
+pub fn bounce_out(t: Param) -> Param {
+    const N: f32 = 7.5625;
+    const D: f32 = 2.75;
+    if t < 1. / D {
+        N * t * t
+    } else if t < 2. / D {
+        let x = t - 1.5 / D;
+        N * x * x + 0.75
+    } else if t < 2.5 / D {
+        let x = t - 2.25 / D;
+        N * x * x + 0.9375
+    } else {
+        let x = t - 2.625 / D;
+        N * x * x + 0.984375
+    }
+}
+
+pub fn bounce_in(t: Param) -> Param {
+    1. - bounce_out(1. - t)
+}
+
+pub fn bounce_in_out(t: Param) -> Param {
+    if t < 0.5 {
+        0.5 * (1. - bounce_out(1. - 2. * t))
+    } else {
+        0.5 * (1. + bounce_out(2. * t - 1.))
+    }
+}
@@ -0,0 +1,272 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+use flight;
+
+/// Anything that can go wrong in the workbench, structured so that callers
+/// can decide how to react (tell the user, retry, or give up).
+#[derive(Debug)]
+pub enum WorkbenchError {
+    /// A required asset file does not exist.
+    // Only scene files are read with `std::fs`, so only they can be missing
+    #[cfg_attr(not(feature = "scene"), allow(dead_code))]
+    AssetMissing {
+        path: PathBuf,
+    },
+    /// An asset file exists but could not be loaded.
+    AssetParse {
+        path: PathBuf,
+        detail: String,
+    },
+    /// A scene file was read, but describes something that can't be built.
+    #[cfg_attr(not(feature = "scene"), allow(dead_code))]
+    SceneValidation(Vec<LoadWarning>),
+    /// The VR runtime misbehaved. Transient errors are expected to go away on
+    /// their own in a later frame.
+    VrRuntime {
+        transient: bool,
+        detail: String,
+    },
+    /// A frame took longer than it was allowed to.
+    BudgetExceeded(BudgetOverrun),
+    /// A bug or an unexpected failure in a dependency.
+    Internal {
+        context: String,
+        source: Option<Box<Error + Send + Sync>>,
+    },
+}
+
+use self::WorkbenchError::*;
+
+/// One problem found while checking a loaded file.
+#[derive(Clone, Debug, PartialEq)]
+pub struct LoadWarning {
+    /// What the problem is with, e.g. `object 2 (Cube)`.
+    pub subject: String,
+    pub detail: String,
+}
+
+impl fmt::Display for LoadWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}: {}", self.subject, self.detail)
+    }
+}
+
+/// How far over its budget a frame ran.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct BudgetOverrun {
+    /// The kind of frame, as passed to `WorkbenchObserver::frame_budget_exceeded`.
+    pub class: &'static str,
+    /// Seconds the frame took.
+    pub spent: f32,
+    /// Seconds the frame was allowed.
+    pub budget: f32,
+}
+
+impl WorkbenchError {
+    /// Build the appropriate error for a failure to read the asset at `path`,
+    /// going by the kind of `err`.
+    #[cfg_attr(not(feature = "scene"), allow(dead_code))]
+    pub fn asset<P: AsRef<Path>>(path: P, err: io::Error) -> WorkbenchError {
+        let path = path.as_ref().to_path_buf();
+        match err.kind() {
+            io::ErrorKind::NotFound => AssetMissing { path: path },
+            _ => AssetParse {
+                path: path,
+                detail: err.to_string(),
+            },
+        }
+    }
+
+    /// Build the error for an asset at `path` that was read but could not be
+    /// made sense of.
+    pub fn asset_parse<P: AsRef<Path>, D: fmt::Display>(path: P, detail: D) -> WorkbenchError {
+        AssetParse {
+            path: path.as_ref().to_path_buf(),
+            detail: detail.to_string(),
+        }
+    }
+
+    /// Build an internal error with no underlying cause.
+    pub fn internal<C: Into<String>>(context: C) -> WorkbenchError {
+        Internal {
+            context: context.into(),
+            source: None,
+        }
+    }
+
+    /// A short, stable name for the kind of error, for use in logs and by
+    /// observers.
+    pub fn category(&self) -> &'static str {
+        match *self {
+            AssetMissing { .. } => "asset_missing",
+            AssetParse { .. } => "asset_parse",
+            SceneValidation(_) => "scene_validation",
+            VrRuntime { .. } => "vr_runtime",
+            BudgetExceeded(_) => "budget_exceeded",
+            Internal { .. } => "internal",
+        }
+    }
+
+    /// Check if retrying the failed operation later might succeed.
+    pub fn is_transient(&self) -> bool {
+        match *self {
+            VrRuntime { transient, .. } => transient,
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for WorkbenchError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            AssetMissing { ref path } =>
+                write!(f, "asset \"{}\" does not exist", path.display()),
+            AssetParse { ref path, ref detail } =>
+                write!(f, "could not load asset \"{}\": {}", path.display(), detail),
+            SceneValidation(ref warnings) => {
+                write!(f, "invalid scene")?;
+                for (i, w) in warnings.iter().enumerate() {
+                    write!(f, "{} {}", if i == 0 { ":" } else { ";" }, w)?;
+                }
+                Ok(())
+            },
+            VrRuntime { transient, ref detail } =>
+                write!(f, "{}VR runtime error: {}", if transient { "transient " } else { "" }, detail),
+            BudgetExceeded(ref o) =>
+                write!(f, "{} frame took {:.1}ms, over its {:.1}ms budget", o.class, o.spent * 1e3, o.budget * 1e3),
+            Internal { ref context, source: Some(ref e) } =>
+                write!(f, "{}: {}", context, e),
+            Internal { ref context, source: None } =>
+                write!(f, "{}", context),
+        }
+    }
+}
+
+impl Error for WorkbenchError {
+    fn description(&self) -> &str {
+        match *self {
+            AssetMissing { .. } => "asset does not exist",
+            AssetParse { .. } => "could not load asset",
+            SceneValidation(_) => "invalid scene",
+            VrRuntime { .. } => "VR runtime error",
+            BudgetExceeded(_) => "frame over budget",
+            Internal { .. } => "internal error",
+        }
+    }
+
+    fn cause(&self) -> Option<&Error> {
+        match *self {
+            Internal { source: Some(ref e), .. } => Some(&**e),
+            _ => None,
+        }
+    }
+}
+
+impl From<flight::Error> for WorkbenchError {
+    fn from(e: flight::Error) -> WorkbenchError {
+        Internal {
+            context: "flight error".into(),
+            source: Some(Box::new(e)),
+        }
+    }
+}
+
+impl From<io::Error> for WorkbenchError {
+    fn from(e: io::Error) -> WorkbenchError {
+        Internal {
+            context: "I/O error".into(),
+            source: Some(Box::new(e)),
+        }
+    }
+}
+
+/// Attach context to errors at fallible boundaries.
+pub trait ResultExt<T> {
+    /// Wrap the error in `WorkbenchError::Internal` with the given context.
+    fn context<C: Into<String>>(self, context: C) -> Result<T, WorkbenchError>;
+}
+
+impl<T, E: Error + Send + Sync + 'static> ResultExt<T> for Result<T, E> {
+    fn context<C: Into<String>>(self, context: C) -> Result<T, WorkbenchError> {
+        self.map_err(|e| Internal {
+            context: context.into(),
+            source: Some(Box::new(e)),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
+    use std::io;
+
+    #[test]
+    fn io_errors_keep_their_source() {
+        let err: WorkbenchError = io::Error::new(io::ErrorKind::NotFound, "gone").into();
+        assert_eq!(err.cause().unwrap().to_string(), "gone");
+        assert_eq!(err.to_string(), "I/O error: gone");
+    }
+
+    #[test]
+    fn context_wraps_any_error() {
+        let res: Result<(), io::Error> = Err(io::Error::new(io::ErrorKind::Other, "disk full"));
+        let err = res.context("saving layout").unwrap_err();
+        assert_eq!(err.to_string(), "saving layout: disk full");
+        assert!(err.cause().is_some());
+    }
+
+    #[test]
+    fn missing_assets_name_the_path() {
+        let err = WorkbenchError::asset(
+            "assets/gone.obj",
+            io::Error::new(io::ErrorKind::NotFound, "whatever"),
+        );
+        assert_eq!(err.category(), "asset_missing");
+        assert!(err.to_string().contains("assets/gone.obj"));
+    }
+
+    #[test]
+    fn unreadable_assets_name_the_path_and_detail() {
+        let err = WorkbenchError::asset(
+            "assets/locked.obj",
+            io::Error::new(io::ErrorKind::PermissionDenied, "not allowed"),
+        );
+        assert_eq!(err.category(), "asset_parse");
+        let msg = err.to_string();
+        assert!(msg.contains("assets/locked.obj") && msg.contains("not allowed"));
+    }
+
+    #[test]
+    fn unparseable_assets_name_the_path_and_detail() {
+        let err = WorkbenchError::asset_parse("assets/scene.ron", "bad header");
+        assert_eq!(err.category(), "asset_parse");
+        let msg = err.to_string();
+        assert!(msg.contains("assets/scene.ron") && msg.contains("bad header"));
+    }
+
+    #[test]
+    fn scene_validation_lists_every_warning() {
+        let err = SceneValidation(vec![
+            LoadWarning { subject: "object 0 (Cube)".into(), detail: "size 0 is not a positive number".into() },
+            LoadWarning { subject: "object 3 (Plane)".into(), detail: "unknown material \"wood\"".into() },
+        ]);
+        assert_eq!(err.category(), "scene_validation");
+        assert_eq!(
+            err.to_string(),
+            "invalid scene: object 0 (Cube): size 0 is not a positive number; \
+             object 3 (Plane): unknown material \"wood\"",
+        );
+    }
+
+    #[test]
+    fn budget_overruns_show_times() {
+        let err = BudgetExceeded(BudgetOverrun { class: "interactive", spent: 0.025, budget: 0.011 });
+        assert_eq!(err.category(), "budget_exceeded");
+        assert_eq!(err.to_string(), "interactive frame took 25.0ms, over its 11.0ms budget");
+        assert!(!err.is_transient());
+    }
+}
@@ -1,10 +1,12 @@
-use ncollide::query::{RayCast, RayIntersection, Ray};
-use nalgebra::{Point3, Vector3, Isometry3};
+use ncollide::query::{RayCast, Ray, PointQuery};
+use nalgebra::{self as na, Point3, Vector3, Isometry3};
 use flight::vr::{Trackable, ViveController};
-use std::collections::BinaryHeap;
-use std::sync::{Arc, Mutex};
+use std::collections::{BinaryHeap, VecDeque};
+use std::sync::{Arc, Mutex, MutexGuard};
 use std::cmp::{Ord, PartialOrd, PartialEq, Ordering};
 
+use error::WorkbenchError;
+
 pub struct VrGuru {
     pub primary: ControllerGuru,
     pub secondary: ControllerGuru,
@@ -13,95 +15,437 @@ pub struct VrGuru {
 impl VrGuru {
     pub fn new(primary: &ViveController, secondary: &ViveController) -> VrGuru {
         VrGuru {
-            primary: ControllerGuru {
-                data: ViveController {
-                    .. *primary
-                },
-                queries: BinaryHeap::new(),
-                blocked: false,
-                laser_toi: None,
-            },
-            secondary: ControllerGuru {
-                data: ViveController {
-                    .. *secondary
-                },
-                queries: BinaryHeap::new(),
-                blocked: false,
-                laser_toi: None,
-            },
+            primary: ControllerGuru::new(Hand::Primary, primary),
+            secondary: ControllerGuru::new(Hand::Secondary, secondary),
         }
     }
     
-    pub fn apply(self) {
-        self.primary.apply();
-        self.secondary.apply();
+    pub fn hand(&mut self, hand: Hand) -> &mut ControllerGuru {
+        match hand {
+            Hand::Primary => &mut self.primary,
+            Hand::Secondary => &mut self.secondary,
+        }
+    }
+
+    pub fn apply(self, memory: &mut GuruMemory) -> GuruOutput {
+        let mut out = self.primary.apply(&mut memory.primary);
+        out.merge(self.secondary.apply(&mut memory.secondary));
+        out
+    }
+
+    /// Like `apply`, but also describe how the queries were resolved.
+    pub fn apply_with_debug(self, memory: &mut GuruMemory) -> (GuruOutput, GuruDebugInfo) {
+        let (mut out, primary) = self.primary.apply_with_debug(&mut memory.primary);
+        let (other, secondary) = self.secondary.apply_with_debug(&mut memory.secondary);
+        out.merge(other);
+        (out, GuruDebugInfo {
+            primary: primary,
+            secondary: secondary,
+        })
+    }
+}
+
+/// How the queries of both controllers were resolved.
+pub struct GuruDebugInfo {
+    pub primary: ControllerDebug,
+    pub secondary: ControllerDebug,
+}
+
+/// How the queries of one controller were resolved, e.g. for drawing.
+pub struct ControllerDebug {
+    /// Where the laser starts and which way it points.
+    pub origin: Point3<f32>,
+    pub pointing: Vector3<f32>,
+    pub laser_toi: Option<f32>,
+    /// Where each pointing query hit, its distance along the laser, and
+    /// whether the hit was answered or hidden behind a nearer one.
+    pub pointing_hits: Vec<(Point3<f32>, f32, bool)>,
+    /// Where each touched shape is, the distance to it, and whether it was
+    /// answered.
+    pub touches: Vec<(Point3<f32>, f32, bool)>,
+    /// Whether pointing and touching were blocked.
+    pub pointing_blocked: bool,
+    pub touching_blocked: bool,
+}
+
+/// Identifies an object to the gurus, so that they can tell when the
+/// controllers start or stop interacting with it. Chosen by the caller.
+pub type ObjectId = usize;
+
+/// Something that started or stopped happening between the controllers and
+/// the objects, e.g. for playing sounds.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum InteractionEvent {
+    /// The laser reached an object.
+    HoverStart { hand: Hand, object_id: ObjectId },
+    /// The laser left an object.
+    HoverEnd { hand: Hand, object_id: ObjectId },
+    /// A controller started holding an object.
+    GrabStart { hand: Hand, object_id: ObjectId },
+    /// A controller stopped holding an object.
+    GrabEnd { hand: Hand, object_id: ObjectId },
+    /// The user teleported this far.
+    Teleport { distance: f32 },
+}
+
+/// What a controller was doing last frame, so that the guru can report what
+/// changed.
+pub struct ControllerMemory {
+    hovered: Option<ObjectId>,
+    held: Vec<ObjectId>,
+}
+
+impl ControllerMemory {
+    pub fn new() -> ControllerMemory {
+        ControllerMemory {
+            hovered: None,
+            held: Vec::new(),
+        }
+    }
+
+    /// Remember what the controller is doing this frame, and report the
+    /// differences from last frame to `out`.
+    fn update(&mut self, hand: Hand, hovered: Option<ObjectId>, mut held: Vec<ObjectId>, out: &mut GuruOutput) {
+        if hovered != self.hovered {
+            if let Some(id) = self.hovered {
+                out.events.push(InteractionEvent::HoverEnd { hand: hand, object_id: id });
+            }
+            if let Some(id) = hovered {
+                out.events.push(InteractionEvent::HoverStart { hand: hand, object_id: id });
+            }
+            self.hovered = hovered;
+        }
+
+        held.sort();
+        held.dedup();
+        for &id in self.held.iter().filter(|&&id| !held.contains(&id)) {
+            out.events.push(InteractionEvent::GrabEnd { hand: hand, object_id: id });
+        }
+        for &id in held.iter().filter(|&&id| !self.held.contains(&id)) {
+            out.events.push(InteractionEvent::GrabStart { hand: hand, object_id: id });
+        }
+        self.held = held;
+    }
+}
+
+/// What both controllers were doing last frame. Keep one around and pass it
+/// to every `VrGuru::apply`.
+pub struct GuruMemory {
+    pub primary: ControllerMemory,
+    pub secondary: ControllerMemory,
+}
+
+impl GuruMemory {
+    pub fn new() -> GuruMemory {
+        GuruMemory {
+            primary: ControllerMemory::new(),
+            secondary: ControllerMemory::new(),
+        }
+    }
+}
+
+/// Identifies one of the controllers.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Hand {
+    Primary,
+    Secondary,
+}
+
+/// Both hands, for looping over.
+pub const HANDS: [Hand; 2] = [Hand::Primary, Hand::Secondary];
+
+impl Hand {
+    /// Get `0` for the primary hand and `1` for the secondary, for indexing
+    /// per-hand arrays.
+    pub fn index(self) -> usize {
+        self as usize
+    }
+}
+
+/// The longest pulse OpenVR will play, in microseconds. No controller is
+/// asked for more than this in total each frame.
+pub const MAX_PULSE_US: u16 = 3999;
+
+/// A vibration to play on a controller.
+#[derive(Clone, Copy, Debug)]
+pub struct Pulse {
+    pub duration_us: u16,
+    /// From `0` (nothing) to `1` (strongest).
+    pub strength: f32,
+}
+
+/// A request to vibrate a controller.
+#[derive(Clone, Copy, Debug)]
+pub struct HapticRequest {
+    pub controller: Hand,
+    pub duration_us: u16,
+    pub strength: f32,
+}
+
+impl HapticRequest {
+    pub fn new(controller: Hand, pulse: Pulse) -> HapticRequest {
+        HapticRequest {
+            controller: controller,
+            duration_us: pulse.duration_us,
+            strength: pulse.strength,
+        }
+    }
+}
+
+/// Shorten or drop requests so that each controller gets at most
+/// `MAX_PULSE_US` of vibration. Earlier requests take priority.
+pub fn limit_pulses(requests: &mut Vec<HapticRequest>) {
+    let (mut primary, mut secondary) = (MAX_PULSE_US, MAX_PULSE_US);
+    for r in requests.iter_mut() {
+        let budget = match r.controller {
+            Hand::Primary => &mut primary,
+            Hand::Secondary => &mut secondary,
+        };
+        r.duration_us = r.duration_us.min(*budget);
+        *budget -= r.duration_us;
+    }
+    requests.retain(|r| r.duration_us > 0);
+}
+
+/// What the gurus want done once their queries have been answered.
+pub struct GuruOutput {
+    pub haptics: Vec<HapticRequest>,
+    pub events: Vec<InteractionEvent>,
+}
+
+impl GuruOutput {
+    pub fn new() -> GuruOutput {
+        GuruOutput {
+            haptics: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    pub fn merge(&mut self, other: GuruOutput) {
+        self.haptics.extend(other.haptics);
+        self.events.extend(other.events);
     }
 }
 
-struct ControllerQuery {
-    hit: RayIntersection<Vector3<f32>>,
-    reply: PointingReply,
+/// A pending query, ordered so that the nearest (smallest `dist`) is popped
+/// first from a `BinaryHeap`. A `NaN` distance, e.g. from a degenerate ray,
+/// is popped last.
+struct ControllerQuery<H> {
+    dist: f32,
+    /// Where the query is, for debugging.
+    at: Point3<f32>,
+    hit: H,
+    reply: Anywhere<Option<H>>,
     stop: bool,
+    haptic: Option<HapticRequest>,
+    id: Option<ObjectId>,
 }
 
-impl PartialEq for ControllerQuery {
-    fn eq(&self, other: &ControllerQuery) -> bool {
+impl<H> PartialEq for ControllerQuery<H> {
+    fn eq(&self, other: &ControllerQuery<H>) -> bool {
         Arc::ptr_eq(&self.reply.0, &other.reply.0)
     }
 }
 
-impl Eq for ControllerQuery {}
+impl<H> Eq for ControllerQuery<H> {}
+
+impl<H> Ord for ControllerQuery<H> {
+    fn cmp(&self, other: &ControllerQuery<H>) -> Ordering {
+        match (self.dist.is_nan(), other.dist.is_nan()) {
+            (true, true) => Ordering::Equal,
+            (true, false) => Ordering::Less,
+            (false, true) => Ordering::Greater,
+            (false, false) => other.dist.partial_cmp(&self.dist).unwrap_or(Ordering::Equal),
+        }
+    }
+}
 
-impl Ord for ControllerQuery {
-    fn cmp(&self, other: &ControllerQuery) -> Ordering {
-        self.partial_cmp(other).expect("TOI can't be NaN")
+impl<H> PartialOrd for ControllerQuery<H> {
+    fn partial_cmp(&self, other: &ControllerQuery<H>) -> Option<Ordering> {
+        Some(self.cmp(other))
     }
 }
 
-impl PartialOrd for ControllerQuery {
-    fn partial_cmp(&self, other: &ControllerQuery) -> Option<Ordering> {
-        other.hit.toi.partial_cmp(&self.hit.toi)
+impl<H> ControllerQuery<H> {
+    /// Check if the query is about one of the `excluded` objects.
+    fn excluded(&self, excluded: &[ObjectId]) -> bool {
+        self.id.map_or(false, |id| excluded.contains(&id))
     }
 }
 
+/// Answer queries nearest first. Every query up to and including the first
+/// stopping one gets its hit, the rest get `None`. Queries about `excluded`
+/// objects always get `None`, and don't stop the ones behind them. The
+/// haptics of queries that hit are added to `out`, and where each query is,
+/// its distance and whether it was answered to `debug`, if given. Returns
+/// the id of the nearest object hit, if any was given one.
+fn resolve<H>(
+    mut queries: BinaryHeap<ControllerQuery<H>>,
+    excluded: &[ObjectId],
+    out: &mut GuruOutput,
+    mut debug: Option<&mut Vec<(Point3<f32>, f32, bool)>>,
+)
+    -> Option<ObjectId>
+{
+    let mut nearest = None;
+    while let Some(q) = queries.pop() {
+        if q.excluded(excluded) {
+            q.reply.put(None);
+            continue;
+        }
+        if let Some(ref mut d) = debug { d.push((q.at, q.dist, true)) }
+        q.reply.put(Some(q.hit));
+        out.haptics.extend(q.haptic);
+        nearest = nearest.or(q.id);
+        if q.stop { break; }
+    }
+    for q in queries {
+        if let Some(ref mut d) = debug { d.push((q.at, q.dist, false)) }
+        q.reply.put(None);
+    }
+    nearest
+}
+
 pub struct ControllerGuru {
+    pub hand: Hand,
     pub data: ViveController,
+    /// The distance along the laser to the nearest thing it hits.
     pub laser_toi: Option<f32>,
-    queries: BinaryHeap<ControllerQuery>,
+    /// The id given for the nearest thing the laser hits, if any.
+    pub laser_id: Option<ObjectId>,
+    /// How far along the ray the visible laser should begin, so that it
+    /// emerges from the far side of held objects instead of clipping
+    /// through them.
+    pub laser_start: f32,
+    queries: BinaryHeap<ControllerQuery<PointingHit>>,
     blocked: bool,
+    touches: BinaryHeap<ControllerQuery<f32>>,
+    touch_blocked: bool,
+    /// Objects held by this controller. Queries about them are never
+    /// answered, so that they don't hide what's behind them.
+    held: Vec<ObjectId>,
+    /// Where rays start instead of the controller, if they were lifted.
+    ray_origin: Option<Point3<f32>>,
+}
+
+/// Where a pointing query hit its shape.
+#[derive(Clone, Copy, Debug)]
+pub struct PointingHit {
+    /// The distance along the ray to the hit.
+    pub toi: f32,
+    /// The hit point in world space.
+    pub point: Point3<f32>,
+    /// The hit point in the space of the shape.
+    pub local_point: Point3<f32>,
 }
 
-pub type PointingReply = Anywhere<Option<RayIntersection<Vector3<f32>>>>;
+pub type PointingReply = Anywhere<Option<PointingHit>>;
+/// The distance from the controller to the touched shape, zero if inside.
+pub type TouchingReply = Anywhere<Option<f32>>;
 impl ControllerGuru {
+    pub fn new(hand: Hand, data: &ViveController) -> ControllerGuru {
+        ControllerGuru {
+            hand: hand,
+            data: ViveController {
+                .. *data
+            },
+            laser_toi: None,
+            laser_id: None,
+            laser_start: 0.,
+            queries: BinaryHeap::new(),
+            blocked: false,
+            touches: BinaryHeap::new(),
+            touch_blocked: false,
+            held: Vec::new(),
+            ray_origin: None,
+        }
+    }
+
+    /// Get the ray that the laser and pointing queries are cast along.
+    pub fn ray(&self) -> Ray<Point3<f32>> {
+        Ray::new(self.ray_origin.unwrap_or(self.data.origin()), self.data.pointing())
+    }
+
+    /// Start rays at `origin` instead of the controller, still pointing the
+    /// same way, e.g. to aim over a big held object. Must be called before
+    /// any queries.
+    pub fn lift_ray(&mut self, origin: Point3<f32>) {
+        self.ray_origin = Some(origin);
+    }
+
+    /// Stop the laser at a shape if it's the nearest so far. `id` is
+    /// recorded in `laser_id` if it is.
     pub fn laser<S: RayCast<Point3<f32>, Isometry3<f32>>>(
         &mut self,
+        id: Option<ObjectId>,
         pos: &Isometry3<f32>,
         shape: &S,
     ) {
-        let ray = Ray::new(self.data.origin(), self.data.pointing());
-        self.laser_toi = match (shape.toi_with_ray(pos, &ray, true), self.laser_toi) {
-            (Some(t), Some(o)) if t < o => Some(t),
-            (Some(t), None) => Some(t),
-            (_, o) => o,
-        };
+        let ray = self.ray();
+        if let Some(t) = shape.toi_with_ray(pos, &ray, true) {
+            if self.laser_toi.map_or(true, |o| t < o) {
+                self.laser_toi = Some(t);
+                self.laser_id = id;
+            }
+        }
+    }
+
+    /// Register a shape held by this controller. Held shapes are excluded
+    /// from the laser, which instead starts where the ray leaves them, and
+    /// from this controller's pointing and touching queries. `reach` is any
+    /// distance along the ray that is past the far side of the shape.
+    pub fn hold<S: RayCast<Point3<f32>, Isometry3<f32>>>(
+        &mut self,
+        id: ObjectId,
+        pos: &Isometry3<f32>,
+        shape: &S,
+        reach: f32,
+    ) {
+        self.held.push(id);
+
+        // Cast back towards the controller from beyond the shape to find
+        // where the ray exits it
+        let ray = self.ray();
+        let back = Ray::new(ray.origin + ray.dir * reach, -ray.dir);
+        if let Some(t) = shape.toi_with_ray(pos, &back, true) {
+            self.laser_start = self.laser_start.max(reach - t);
+        }
     }
 
+    /// Query if the laser hits a shape. Only the nearest hit is answered if
+    /// `stops` is true. If the query hits, `haptic_on_hit` is played; pass
+    /// `None` if the shape was already hit last frame so that the pulse only
+    /// plays when the laser first reaches it. Hover events are only reported
+    /// for shapes with an `id`.
     pub fn pointing<S: RayCast<Point3<f32>, Isometry3<f32>>>(
         &mut self,
+        id: Option<ObjectId>,
         pos: &Isometry3<f32>,
         shape: &S,
         stops: bool,
+        haptic_on_hit: Option<Pulse>,
     )
         -> PointingReply 
     {
         if !self.blocked {
-            let ray = Ray::new(self.data.origin(), self.data.pointing());
-            if let Some(i) = shape.toi_and_normal_with_ray(pos, &ray, true) {
+            let ray = self.ray();
+            if let Some(toi) = shape.toi_with_ray(pos, &ray, true) {
+                let point = ray.origin + ray.dir * toi;
+                let hit = PointingHit {
+                    toi: toi,
+                    point: point,
+                    local_point: pos.inverse() * point,
+                };
+                let hand = self.hand;
                 let reply = Anywhere::new();
                 self.queries.push(ControllerQuery {
-                    hit: i,
+                    dist: hit.toi,
+                    at: point,
+                    hit: hit,
                     reply: reply.clone(),
                     stop: stops,
+                    haptic: haptic_on_hit.map(|p| HapticRequest::new(hand, p)),
+                    id: id,
                 });
                 return reply;
             }
@@ -116,15 +460,157 @@ impl ControllerGuru {
         }
     }
 
-    pub fn apply(mut self) {
-        while let Some(q) = self.queries.pop() {
-            q.reply.put(Some(q.hit));
-            if q.stop { break; }
+    /// Query if the controller is touching a shape, meaning its origin is
+    /// inside or within `margin` of it. Like pointing, the nearest shape
+    /// wins if `stops` is true.
+    pub fn touching<S: PointQuery<Point3<f32>, Isometry3<f32>>>(
+        &mut self,
+        id: Option<ObjectId>,
+        pos: &Isometry3<f32>,
+        shape: &S,
+        margin: f32,
+        stops: bool,
+    )
+        -> TouchingReply
+    {
+        if !self.touch_blocked {
+            let dist = shape.distance_to_point(pos, &self.data.origin(), true);
+            if dist <= margin {
+                let reply = Anywhere::new();
+                self.touches.push(ControllerQuery {
+                    dist: dist,
+                    at: Point3::from_coordinates(pos.translation.vector),
+                    hit: dist,
+                    reply: reply.clone(),
+                    stop: stops,
+                    haptic: None,
+                    id: id,
+                });
+                return reply;
+            }
         }
-        for q in self.queries {
+        Anywhere::with(None)
+    }
+
+    /// Check if any touching queries are waiting to be answered, other than
+    /// ones about held objects.
+    pub fn touching_any(&self) -> bool {
+        self.touches.iter().any(|q| !q.excluded(&self.held))
+    }
+
+    pub fn block_touching(&mut self) {
+        self.touch_blocked = true;
+        for q in self.touches.drain() {
             q.reply.put(None);
         }
     }
+
+    /// Answer all queries, and report what changed since the frame that
+    /// `memory` was last applied with.
+    pub fn apply(self, memory: &mut ControllerMemory) -> GuruOutput {
+        let mut out = GuruOutput::new();
+        let hovered = resolve(self.queries, &self.held, &mut out, None);
+        resolve(self.touches, &self.held, &mut out, None);
+        memory.update(self.hand, hovered, self.held, &mut out);
+        out
+    }
+
+    /// Like `apply`, but also describe how the queries were resolved.
+    pub fn apply_with_debug(self, memory: &mut ControllerMemory) -> (GuruOutput, ControllerDebug) {
+        let mut debug = ControllerDebug {
+            origin: self.ray().origin,
+            pointing: self.data.pointing(),
+            laser_toi: self.laser_toi,
+            pointing_hits: Vec::new(),
+            touches: Vec::new(),
+            pointing_blocked: self.blocked,
+            touching_blocked: self.touch_blocked,
+        };
+        let mut out = GuruOutput::new();
+        let hovered = resolve(self.queries, &self.held, &mut out, Some(&mut debug.pointing_hits));
+        resolve(self.touches, &self.held, &mut out, Some(&mut debug.touches));
+        memory.update(self.hand, hovered, self.held, &mut out);
+        (out, debug)
+    }
+}
+
+/// The number of poses remembered by a `ControllerHistory`.
+const HISTORY_LEN: usize = 8;
+/// Only poses this many seconds older than the newest are used to estimate
+/// velocity.
+const HISTORY_WINDOW: f32 = 0.1;
+/// Movement faster than this (in m/s) is assumed to be a tracking glitch.
+const MAX_LINEAR_SPEED: f32 = 20.;
+/// Rotation faster than this (in rad/s) is assumed to be a tracking glitch.
+const MAX_ANGULAR_SPEED: f32 = 50.;
+
+/// Remembers recent controller poses to estimate how fast the controller is
+/// moving, e.g. so that released objects can be thrown.
+pub struct ControllerHistory {
+    poses: VecDeque<(f32, Isometry3<f32>)>,
+}
+
+impl ControllerHistory {
+    pub fn new() -> ControllerHistory {
+        ControllerHistory {
+            poses: VecDeque::with_capacity(HISTORY_LEN),
+        }
+    }
+
+    /// Record the controller pose at the given time in seconds. Times must
+    /// increase, but need not be evenly spaced.
+    pub fn push(&mut self, time: f32, pose: Isometry3<f32>) {
+        if let Some(&(last, _)) = self.poses.back() {
+            if time <= last { return }
+        }
+        if self.poses.len() >= HISTORY_LEN {
+            self.poses.pop_front();
+        }
+        self.poses.push_back((time, pose));
+    }
+
+    /// Forget all poses, e.g. when tracking is lost.
+    pub fn clear(&mut self) {
+        self.poses.clear();
+    }
+
+    /// Average `f` over each pair of consecutive poses in the window,
+    /// skipping pairs where `f` gives `None`.
+    fn average<F>(&self, f: F) -> Vector3<f32>
+        where F: Fn(&Isometry3<f32>, &Isometry3<f32>, f32) -> Option<Vector3<f32>>
+    {
+        let newest = match self.poses.back() {
+            Some(&(t, _)) => t,
+            None => return na::zero(),
+        };
+        let mut sum = na::zero::<Vector3<f32>>();
+        let mut count = 0;
+        for (&(t0, ref p0), &(t1, ref p1)) in self.poses.iter().zip(self.poses.iter().skip(1)) {
+            if newest - t0 > HISTORY_WINDOW { continue }
+            if let Some(v) = f(p0, p1, t1 - t0) {
+                sum += v;
+                count += 1;
+            }
+        }
+        if count == 0 { na::zero() } else { sum / count as f32 }
+    }
+
+    /// Estimate the velocity of the controller origin in m/s.
+    pub fn linear_velocity(&self) -> Vector3<f32> {
+        self.average(|p0, p1, dt| {
+            let v = (p1.translation.vector - p0.translation.vector) / dt;
+            if v.norm() > MAX_LINEAR_SPEED { None } else { Some(v) }
+        })
+    }
+
+    /// Estimate the angular velocity of the controller as a scaled axis in
+    /// rad/s.
+    pub fn angular_velocity(&self) -> Vector3<f32> {
+        self.average(|p0, p1, dt| {
+            let w = (p1.rotation * p0.rotation.inverse()).scaled_axis() / dt;
+            if w.norm() > MAX_ANGULAR_SPEED { None } else { Some(w) }
+        })
+    }
 }
 
 pub struct Anywhere<T>(Arc<Mutex<Option<T>>>);
@@ -144,16 +630,24 @@ impl<T> Anywhere<T> {
         From::from(Some(v))
     }
 
+    // A panic while the lock is held can't leave the `Option` half-written,
+    // so a poisoned lock is safe to keep using.
+    fn lock(&self) -> MutexGuard<Option<T>> {
+        self.0.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
     pub fn take(&self) -> Option<T> { 
-        self.0.lock().unwrap().take()
+        self.lock().take()
     }
 
     pub fn put(&self, v: T) {
-        *self.0.lock().unwrap() = Some(v);
+        *self.lock() = Some(v);
     }
 
-    pub fn expect(&self, msg: &str) -> T {
-        self.take().expect(msg)
+    /// Take the value, or fail with an internal error if nothing has been put
+    /// yet. `context` should say what was expected.
+    pub fn get(&self, context: &str) -> Result<T, WorkbenchError> {
+        self.take().ok_or_else(|| WorkbenchError::internal(context))
     }
 }
 
@@ -162,3 +656,277 @@ impl<T> From<Option<T>> for Anywhere<T> {
         Anywhere(Arc::new(Mutex::new(v)))  
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::f32::consts::{FRAC_PI_2, FRAC_PI_4};
+    use std::thread;
+    use ncollide::shape::Cuboid3;
+
+    #[test]
+    fn nan_distances_are_resolved_last() {
+        let mut queries = BinaryHeap::new();
+        for &dist in &[0.5, ::std::f32::NAN, 0.2, ::std::f32::NAN, 1.] {
+            queries.push(ControllerQuery {
+                dist: dist,
+                at: Point3::origin(),
+                hit: 0,
+                reply: Anywhere::new(),
+                stop: false,
+                haptic: None,
+                id: None,
+            });
+        }
+        let mut debug = Vec::new();
+        resolve(queries, &[], &mut GuruOutput::new(), Some(&mut debug));
+        let dists: Vec<f32> = debug.iter().map(|&(_, d, _)| d).collect();
+        assert_eq!(&dists[..3], &[0.2, 0.5, 1.]);
+        assert!(dists[3].is_nan() && dists[4].is_nan());
+    }
+
+    /// A guru for a controller at `pose`, pointing along its `-z`.
+    fn guru_at(pose: Isometry3<f32>) -> ControllerGuru {
+        ControllerGuru::new(Hand::Primary, &ViveController {
+            pose: pose,
+            .. Default::default()
+        })
+    }
+
+    fn at(x: f32, y: f32, z: f32) -> Isometry3<f32> {
+        Isometry3::new(Vector3::new(x, y, z), na::zero())
+    }
+
+    fn cube(radius: f32) -> Cuboid3<f32> {
+        Cuboid3::new(Vector3::from_element(radius))
+    }
+
+    #[test]
+    fn held_objects_dont_hide_whats_behind_them() {
+        let (big, small) = (cube(0.4), cube(0.1));
+        let mut g = guru_at(na::one());
+        g.hold(0, &at(0., 0., -0.3), &big, 2.);
+        let held = g.pointing(Some(0), &at(0., 0., -0.3), &big, true, None);
+        let held_touch = g.touching(Some(0), &at(0., 0., -0.3), &big, 0.02, true);
+        assert!(!g.touching_any());
+        g.laser(Some(1), &at(0., 0., -2.), &small);
+        let behind = g.pointing(Some(1), &at(0., 0., -2.), &small, true, None);
+        assert_eq!(g.laser_id, Some(1));
+
+        // The other hand isn't holding it, so it still sees the cube
+        let mut other = guru_at(na::one());
+        other.hand = Hand::Secondary;
+        let seen = other.pointing(Some(0), &at(0., 0., -0.3), &big, true, None);
+        other.pointing(Some(1), &at(0., 0., -2.), &small, true, None);
+
+        let out = g.apply(&mut ControllerMemory::new());
+        assert!(held.take().unwrap().is_none());
+        assert!(held_touch.take().unwrap().is_none());
+        let hit = behind.take().unwrap().unwrap();
+        assert!((hit.toi - 1.9).abs() < 1e-5);
+        assert_eq!(out.events, vec![
+            InteractionEvent::HoverStart { hand: Hand::Primary, object_id: 1 },
+            InteractionEvent::GrabStart { hand: Hand::Primary, object_id: 0 },
+        ]);
+
+        let out = other.apply(&mut ControllerMemory::new());
+        assert!(seen.take().unwrap().is_some());
+        assert_eq!(out.events, vec![
+            InteractionEvent::HoverStart { hand: Hand::Secondary, object_id: 0 },
+        ]);
+    }
+
+    #[test]
+    fn laser_starts_at_far_side_of_held_object() {
+        let shape = cube(0.3);
+        let mut g = guru_at(na::one());
+        g.hold(0, &at(0., 0., -0.2), &shape, 2.);
+        assert!((g.laser_start - 0.5).abs() < 1e-5);
+
+        // Turned a quarter, the far edge is a diagonal away from the center
+        let mut g = guru_at(na::one());
+        let turned = Isometry3::new(Vector3::new(0., 0., -0.2), Vector3::y() * FRAC_PI_4);
+        g.hold(0, &turned, &shape, 2.);
+        assert!((g.laser_start - (0.2 + 0.3 * 2f32.sqrt())).abs() < 1e-5);
+
+        // Held off to the side, the laser isn't in it at all
+        let mut g = guru_at(na::one());
+        g.hold(0, &at(1., 0., -0.2), &shape, 2.);
+        assert_eq!(g.laser_start, 0.);
+    }
+
+    #[test]
+    fn lifted_ray_aims_over_held_object() {
+        let (big, wall, target) = (cube(0.4), Cuboid3::new(Vector3::new(5., 5., 0.1)), cube(0.05));
+        let lift = 0.8;
+        let query = |g: &mut ControllerGuru| {
+            g.hold(0, &at(0., 0., -0.3), &big, 2.);
+            let wall = g.pointing(Some(1), &at(0., 0., -3.), &wall, false, None);
+            let target = g.pointing(Some(2), &at(0., 0., -1.5), &target, false, None);
+            (wall, target)
+        };
+
+        let mut direct = guru_at(na::one());
+        let (direct_wall, direct_target) = query(&mut direct);
+        let direct_dir = direct.ray().dir;
+        direct.apply(&mut ControllerMemory::new());
+
+        let mut lifted = guru_at(na::one());
+        lifted.lift_ray(Point3::new(0., lift, 0.));
+        let (lifted_wall, lifted_target) = query(&mut lifted);
+        // The lifted laser clears the held cube, so it starts right away
+        assert_eq!(lifted.laser_start, 0.);
+        assert_eq!(lifted.ray().dir, direct_dir);
+        lifted.apply(&mut ControllerMemory::new());
+
+        // Both hit the wall at the same distance, the lifted one exactly as
+        // much higher as it was lifted
+        let (d, l) = (direct_wall.take().unwrap().unwrap(), lifted_wall.take().unwrap().unwrap());
+        assert!((d.toi - 2.9).abs() < 1e-5 && (l.toi - 2.9).abs() < 1e-5);
+        assert!((l.point - d.point - Vector3::new(0., lift, 0.)).norm() < 1e-5);
+        // But a small target in line with the controller is passed over
+        assert!(direct_target.take().unwrap().is_some());
+        assert!(lifted_target.take().unwrap().is_none());
+    }
+
+    #[test]
+    fn anywhere_get_fails_without_a_value() {
+        let a: Anywhere<u32> = Anywhere::new();
+        let err = a.get("pointing reply").unwrap_err();
+        assert_eq!(err.category(), "internal");
+        assert_eq!(err.to_string(), "pointing reply");
+    }
+
+    #[test]
+    fn anywhere_survives_a_poisoned_lock() {
+        let a = Anywhere::with(1u32);
+        let b = a.clone();
+        let res = thread::spawn(move || {
+            let _guard = b.0.lock().unwrap();
+            panic!("poison the lock");
+        }).join();
+        assert!(res.is_err());
+        assert!(a.0.is_poisoned());
+        assert_eq!(a.take(), Some(1));
+        a.put(2);
+        assert_eq!(a.get("value").unwrap(), 2);
+    }
+
+    #[test]
+    fn only_the_nearest_touched_shape_gets_the_touch() {
+        let shape = cube(0.2);
+        let mut g = guru_at(na::one());
+        // The controller is inside the first cube and just outside the second
+        let far = g.touching(Some(1), &at(0., 0., 0.25), &shape, 0.1, true);
+        let near = g.touching(Some(0), &at(0., 0., -0.1), &shape, 0.1, true);
+        let away = g.touching(Some(2), &at(0., 0., 1.), &shape, 0.1, true);
+        assert!(g.touching_any());
+        g.apply(&mut ControllerMemory::new());
+        assert_eq!(near.take().unwrap(), Some(0.));
+        assert_eq!(far.take().unwrap(), None);
+        assert_eq!(away.take().unwrap(), None);
+
+        // A shape that doesn't stop touches lets the next one have it too
+        let mut g = guru_at(na::one());
+        let near = g.touching(Some(0), &at(0., 0., -0.1), &shape, 0.1, false);
+        let far = g.touching(Some(1), &at(0., 0., 0.25), &shape, 0.1, true);
+        g.apply(&mut ControllerMemory::new());
+        assert_eq!(near.take().unwrap(), Some(0.));
+        assert!((far.take().unwrap().unwrap() - 0.05).abs() < 1e-5);
+    }
+
+    #[test]
+    fn pointing_hits_are_described_in_the_shape_too() {
+        let shape = cube(0.5);
+        let pos = Isometry3::new(Vector3::new(0.25, 0., -2.), Vector3::z() * FRAC_PI_2);
+        let mut g = guru_at(na::one());
+        let reply = g.pointing(None, &pos, &shape, true, None);
+        g.apply(&mut ControllerMemory::new());
+        let hit = reply.take().unwrap().unwrap();
+        assert!((hit.toi - 1.5).abs() < 1e-5);
+        assert!((hit.point - Point3::new(0., 0., -1.5)).norm() < 1e-5);
+        // The hit is left of the center, which is up once the cube's quarter
+        // turn is undone
+        assert!((hit.local_point - Point3::new(0., 0.25, 0.5)).norm() < 1e-5);
+    }
+
+    #[test]
+    fn nearest_stopping_hit_hides_the_rest() {
+        let shape = cube(0.1);
+        let mut g = guru_at(na::one());
+        // Registered far first, so the order they were asked in doesn't matter
+        let far = g.pointing(Some(1), &at(0., 0., -3.), &shape, true, None);
+        let near = g.pointing(Some(0), &at(0., 0., -1.), &shape, true, None);
+        let glass = g.pointing(Some(2), &at(0., 0., -0.5), &shape, false, None);
+        let behind_glass = g.pointing(Some(3), &at(0., 0., -2.), &shape, false, None);
+        let out = g.apply(&mut ControllerMemory::new());
+
+        assert!((near.take().unwrap().unwrap().toi - 0.9).abs() < 1e-5);
+        assert!(far.take().unwrap().is_none());
+        // A query that doesn't stop still gets its hit in front of the
+        // stopping one, but not behind it
+        assert!((glass.take().unwrap().unwrap().toi - 0.4).abs() < 1e-5);
+        assert!(behind_glass.take().unwrap().is_none());
+        // The nearest object with an id is hovered, even if it doesn't stop
+        assert_eq!(out.events, vec![
+            InteractionEvent::HoverStart { hand: Hand::Primary, object_id: 2 },
+        ]);
+    }
+
+    #[test]
+    fn hover_moves_to_whatever_is_nearest() {
+        use self::InteractionEvent::*;
+
+        let shape = cube(0.1);
+        let mut memory = ControllerMemory::new();
+        let mut frame = |objects: &[(ObjectId, f32)], held: Option<ObjectId>| {
+            let mut g = guru_at(na::one());
+            for &(id, z) in objects {
+                if held == Some(id) {
+                    g.hold(id, &at(0., 0., z), &shape, 5.);
+                }
+            }
+            for &(id, z) in objects {
+                g.pointing(Some(id), &at(0., 0., z), &shape, true, None);
+            }
+            g.apply(&mut memory).events
+        };
+        let hand = Hand::Primary;
+
+        assert_eq!(frame(&[(0, -2.)], None), vec![HoverStart { hand: hand, object_id: 0 }]);
+        // Nothing changed, so nothing is reported
+        assert_eq!(frame(&[(0, -2.)], None), vec![]);
+        // Something moved in front
+        assert_eq!(frame(&[(0, -2.), (1, -1.)], None), vec![
+            HoverEnd { hand: hand, object_id: 0 },
+            HoverStart { hand: hand, object_id: 1 },
+        ]);
+        // Grabbed, it no longer hides the one behind it
+        assert_eq!(frame(&[(0, -2.), (1, -1.)], Some(1)), vec![
+            HoverEnd { hand: hand, object_id: 1 },
+            HoverStart { hand: hand, object_id: 0 },
+            GrabStart { hand: hand, object_id: 1 },
+        ]);
+        assert_eq!(frame(&[(0, -2.)], None), vec![
+            GrabEnd { hand: hand, object_id: 1 },
+        ]);
+        assert_eq!(frame(&[], None), vec![HoverEnd { hand: hand, object_id: 0 }]);
+    }
+
+    #[test]
+    fn debug_info_says_where_queries_are() {
+        let shape = cube(0.25);
+        let mut g = guru_at(na::one());
+        g.touching(Some(0), &at(0.125, 0., 0.), &shape, 0.25, true);
+        g.touching(Some(1), &at(0., 0.375, 0.), &shape, 0.25, true);
+        g.pointing(Some(2), &at(0., 0., -1.), &shape, true, None);
+        let (_, debug) = g.apply_with_debug(&mut ControllerMemory::new());
+        assert_eq!(debug.touches, vec![
+            (Point3::new(0.125, 0., 0.), 0., true),
+            (Point3::new(0., 0.375, 0.), 0.125, false),
+        ]);
+        let (point, toi, answered) = debug.pointing_hits[0];
+        assert!((point - Point3::new(0., 0., -0.75)).norm() < 1e-5);
+        assert!((toi - 0.75).abs() < 1e-5 && answered);
+    }
+}
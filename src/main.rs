@@ -12,6 +12,10 @@ extern crate glutin;
 extern crate gfx_device_gl;
 extern crate gfx_window_glutin;
 extern crate ncollide;
+#[cfg(feature = "serde")]
+extern crate serde;
+#[cfg(any(test, feature = "scene"))]
+extern crate ron;
 
 use simplelog::{Config, TermLogger, LogLevelFilter};
 use clap::{Arg, App};
@@ -20,28 +24,64 @@ use gfx::format::*;
 use gfx_device_gl::{NewTexture};
 use gfx::memory::Typed;
 use glutin::GlContext;
+use nalgebra::{Isometry3, Vector3};
 
 mod app;
 //mod grid;
 //mod state;
 mod animation;
+mod easing;
 mod interact;
+mod error;
+mod observer;
+mod timing;
+mod snap;
+mod pad;
+mod sim;
+mod debug;
+mod scene;
 
 use flight::draw;
 use flight::vr::*;
 
+use sim::SimController;
+
+/// The vertical field of view assumed for the mock display, in radians.
+const DESK_FOV: f32 = 1.2;
+
 fn main() {
     // Logging setup
     TermLogger::init(LogLevelFilter::Info, Config::default()).unwrap();
 
     // Command line arguments
-    let matches = App::new("VR")
+    let args = App::new("VR")
         .arg(Arg::with_name("mock")
             .short("m")
             .long("mock")
             .help("Use mock VR API"))
-        .get_matches();
+        .arg(Arg::with_name("snap")
+            .short("s")
+            .long("snap")
+            .help("Snap released cubes to a grid"))
+        .arg(Arg::with_name("debug")
+            .short("d")
+            .long("debug")
+            .help("Always show the debug overlay"))
+        .arg(Arg::with_name("lift")
+            .short("l")
+            .long("lift")
+            .help("Aim from above big held cubes"))
+        .arg(Arg::with_name("observe")
+            .long("observe")
+            .help("Log what the user does"));
+    #[cfg(feature = "scene")]
+    let args = args.arg(Arg::with_name("scene")
+        .long("scene")
+        .value_name("FILE")
+        .help("Load the scene from a RON file"));
+    let matches = args.get_matches();
     let mock = matches.is_present("mock");
+    let snap = matches.is_present("snap");
 
     // VR init
     let mut vrctx = match if mock { VrContext::mock() } else { VrContext::new() } {
@@ -94,13 +134,28 @@ fn main() {
     let (.., depth) = factory.create_depth_stencil(render_width as u16, render_height as u16).unwrap();
 
     let surface = factory.view_texture_as_render_target::<(R8_G8_B8_A8, Unorm)>(&tex, 0, None).unwrap();
-    let mut application = match app::App::new(&mut factory) {
+    #[cfg(feature = "scene")]
+    let application = match matches.value_of("scene") {
+        Some(path) => app::App::from_scene(&mut factory, path),
+        None => app::App::new(&mut factory),
+    };
+    #[cfg(not(feature = "scene"))]
+    let application = app::App::new(&mut factory);
+    let mut application = match application {
         Ok(a) => a,
         Err(e) => {
             error!("Could not start application: {}", e);
             return
         },
     };
+    if snap {
+        application.set_snap_grid(Some(snap::SnapGrid::new(nalgebra::one(), 0.25, 8)));
+    }
+    application.set_debug(matches.is_present("debug"));
+    application.set_lift_pointer(matches.is_present("lift"));
+    if matches.is_present("observe") {
+        application.set_observer(Box::new(observer::LoggingObserver));
+    }
     let mut model = application.model();
 
     // setup context
@@ -114,6 +169,21 @@ fn main() {
 
     if mock { window.show() }
 
+    // Without a headset, the mouse aims one controller at a time from the
+    // desk camera. Left click pulls its trigger, the wheel swipes its pad,
+    // and tab switches hands.
+    let camera = sim::desk_camera();
+    let aspect = render_width as f32 / render_height as f32;
+    let proj = sim::desk_projection(
+        &camera, DESK_FOV, aspect, app::NEAR_PLANE as f32, app::FAR_PLANE as f32);
+    // Hold the hands apart, so they don't start inside each other
+    let mut sims = [
+        SimController::at(camera * Isometry3::new(Vector3::new(0.15, -0.2, -0.4), nalgebra::zero())),
+        SimController::at(camera * Isometry3::new(Vector3::new(-0.15, -0.2, -0.4), nalgebra::zero())),
+    ];
+    let mut active = 0;
+    let mut last_frame = std::time::Instant::now();
+
     // Main loop
     vrctx.start();
     let mut running = true;
@@ -126,11 +196,35 @@ fn main() {
 
         // Update context
         running = !vrm.exit;
-        ctx.left = hmd.left;
-        ctx.right = hmd.right;
+        if mock {
+            // Both eyes see what the desk camera sees
+            ctx.left = proj;
+            ctx.right = proj;
+        } else {
+            ctx.left = hmd.left;
+            ctx.right = hmd.right;
+        }
 
         // Draw frame
-        application.draw(&mut ctx, &vrm, &mut model);
+        if mock {
+            let elapsed = last_frame.elapsed();
+            last_frame = std::time::Instant::now();
+            let dt = elapsed.as_secs() as f32 + elapsed.subsec_nanos() as f32 * 1e-9;
+            for sim in &mut sims {
+                sim.update(dt);
+            }
+            application.draw_with_inputs(&mut ctx, &Isometry3::identity(), &sims[0], &sims[1], &mut model);
+        } else {
+            application.draw(&mut ctx, &vrm, &mut model);
+        }
+        // TODO: Send the pulses to the controllers once flight can trigger
+        // them
+        for pulse in application.haptics() {
+            debug!("{:?} pulse for {}us at {:.2}", pulse.controller, pulse.duration_us, pulse.strength);
+        }
+        for event in application.events() {
+            debug!("{:?}", event);
+        }
 
         // Send instructions to OpenGL
         // TODO: Move flush to separate thread
@@ -145,10 +239,29 @@ fn main() {
 
         // Window Events
         events_loop.poll_events(|event| {
+            use glutin::WindowEvent::*;
+            use glutin::{ElementState, MouseButton, MouseScrollDelta, VirtualKeyCode};
+            let event = match event {
+                glutin::Event::WindowEvent { event, .. } => event,
+                _ => return,
+            };
             match event {
                 // process events here
-                glutin::Event::WindowEvent { event: glutin::WindowEvent::Closed, .. } =>
-                    running = false,
+                Closed => running = false,
+                MouseMoved { position: (x, y), .. } if mock => {
+                    let x = x as f32 / render_width as f32 * 2. - 1.;
+                    let y = 1. - y as f32 / render_height as f32 * 2.;
+                    sims[active].aim(&camera, DESK_FOV, aspect, x, y);
+                },
+                MouseInput { state, button: MouseButton::Left, .. } if mock =>
+                    sims[active].press(state == ElementState::Pressed),
+                MouseWheel { delta: MouseScrollDelta::LineDelta(_, y), .. } if mock =>
+                    sims[active].scroll(y),
+                KeyboardInput { input: glutin::KeyboardInput {
+                    state: ElementState::Pressed,
+                    virtual_keycode: Some(VirtualKeyCode::Tab),
+                    ..
+                }, .. } if mock => active = 1 - active,
                 _ => ()
             }
         });
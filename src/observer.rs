@@ -0,0 +1,161 @@
+use std::cell::RefCell;
+use std::panic::{self, AssertUnwindSafe};
+
+use error::WorkbenchError;
+
+/// Receives notifications about high-level happenings in the workbench, so
+/// that an embedding application can collect its own usage data.
+///
+/// Every method has an empty default. Implementations are called from inside
+/// the frame and must not block; anything slow belongs on another thread. An
+/// observer that panics is disabled (and the panic logged) rather than
+/// crashing the frame.
+#[allow(unused_variables)]
+pub trait WorkbenchObserver {
+    /// The first frame of the session is about to be drawn.
+    fn session_started(&mut self) {}
+    /// A model was built containing `object_count` objects.
+    fn scene_loaded(&mut self, object_count: usize) {}
+    /// An object of the given kind was created.
+    fn object_spawned(&mut self, kind: &str) {}
+    /// A grab ended after `duration` seconds, having moved the object
+    /// `distance` meters from where it was picked up.
+    fn grab_completed(&mut self, duration: f32, distance: f32) {}
+    /// The user teleported `distance` meters.
+    fn teleport(&mut self, distance: f32) {}
+    /// Something went wrong that did not stop the application.
+    fn error(&mut self, category: &str, message: &str) {}
+    /// A frame of the given class ran over its time budget.
+    fn frame_budget_exceeded(&mut self, class: &str) {}
+}
+
+/// Ignores everything. This is the observer used if none is set.
+pub struct NoopObserver;
+
+impl WorkbenchObserver for NoopObserver {}
+
+/// Writes every notification to the log, for debugging.
+pub struct LoggingObserver;
+
+impl WorkbenchObserver for LoggingObserver {
+    fn session_started(&mut self) {
+        info!("observer: session started");
+    }
+
+    fn scene_loaded(&mut self, object_count: usize) {
+        info!("observer: scene loaded with {} objects", object_count);
+    }
+
+    fn object_spawned(&mut self, kind: &str) {
+        info!("observer: {} spawned", kind);
+    }
+
+    fn grab_completed(&mut self, duration: f32, distance: f32) {
+        info!("observer: grab completed after {:.2}s, moved {:.2}m", duration, distance);
+    }
+
+    fn teleport(&mut self, distance: f32) {
+        info!("observer: teleported {:.2}m", distance);
+    }
+
+    fn error(&mut self, category: &str, message: &str) {
+        info!("observer: {} error: {}", category, message);
+    }
+
+    fn frame_budget_exceeded(&mut self, class: &str) {
+        info!("observer: {} frame over budget", class);
+    }
+}
+
+/// Holds the current observer and isolates the rest of the application from
+/// it misbehaving.
+pub struct ObserverSlot {
+    inner: RefCell<Option<Box<WorkbenchObserver>>>,
+}
+
+impl ObserverSlot {
+    pub fn new() -> ObserverSlot {
+        ObserverSlot::with(Box::new(NoopObserver))
+    }
+
+    pub fn with(observer: Box<WorkbenchObserver>) -> ObserverSlot {
+        ObserverSlot {
+            inner: RefCell::new(Some(observer)),
+        }
+    }
+
+    /// Replace the current observer, re-enabling notifications if a previous
+    /// observer was disabled.
+    pub fn set(&self, observer: Box<WorkbenchObserver>) {
+        *self.inner.borrow_mut() = Some(observer);
+    }
+
+    /// Tell the observer about an error.
+    pub fn report(&self, err: &WorkbenchError) {
+        self.notify(|o| o.error(err.category(), &err.to_string()));
+    }
+
+    /// Pass a notification to the observer. If the observer panics it is
+    /// dropped and no further notifications are sent.
+    pub fn notify<F: FnOnce(&mut WorkbenchObserver)>(&self, f: F) {
+        let mut inner = match self.inner.try_borrow_mut() {
+            Ok(i) => i,
+            // An observer tried to trigger a notification from inside one
+            Err(_) => return,
+        };
+        let failed = match *inner {
+            Some(ref mut o) => panic::catch_unwind(AssertUnwindSafe(|| f(&mut **o))).is_err(),
+            None => false,
+        };
+        if failed {
+            error!("Workbench observer panicked, disabling it");
+            *inner = None;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    /// Records spawns, and panics on teleports.
+    struct Flaky(Rc<RefCell<Vec<String>>>);
+
+    impl WorkbenchObserver for Flaky {
+        fn object_spawned(&mut self, kind: &str) {
+            self.0.borrow_mut().push(kind.to_string());
+        }
+
+        fn teleport(&mut self, _: f32) {
+            panic!("observer bug");
+        }
+    }
+
+    #[test]
+    fn panicking_observer_is_disabled() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let slot = ObserverSlot::with(Box::new(Flaky(log.clone())));
+        slot.notify(|o| o.object_spawned("cube"));
+        slot.notify(|o| o.teleport(1.));
+        slot.notify(|o| o.object_spawned("cube"));
+        assert_eq!(*log.borrow(), vec!["cube".to_string()]);
+
+        // Setting a new observer turns notifications back on
+        slot.set(Box::new(Flaky(log.clone())));
+        slot.notify(|o| o.object_spawned("plane"));
+        assert_eq!(*log.borrow(), vec!["cube".to_string(), "plane".to_string()]);
+    }
+
+    #[test]
+    fn nested_notifications_are_dropped() {
+        let log = Rc::new(RefCell::new(Vec::new()));
+        let slot = ObserverSlot::with(Box::new(Flaky(log.clone())));
+        slot.notify(|o| {
+            o.object_spawned("outer");
+            slot.notify(|o| o.object_spawned("inner"));
+        });
+        assert_eq!(*log.borrow(), vec!["outer".to_string()]);
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::VecDeque;
+
+/// Thumb movements smaller than this (in pad lengths) are treated as noise.
+const DEADZONE: f32 = 0.03;
+/// How far (in pad lengths) the thumb must move within `SWIPE_WINDOW` to
+/// count as a swipe.
+const SWIPE_THRESHOLD: f32 = 0.3;
+/// How long (in seconds) a swipe may take.
+const SWIPE_WINDOW: f32 = 0.15;
+
+/// Which way the thumb swiped along the pad's `y` axis.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum SwipeDirection {
+    Up,
+    Down,
+}
+
+/// Cleans up touchpad readings, so that a resting thumb doesn't register as
+/// movement. Feed it every frame with `update`.
+pub struct PadFilter {
+    /// Movement inside this radius (in pad lengths) of the filtered position
+    /// is ignored. Larger movements drag the filtered position along,
+    /// staying this far behind.
+    pub deadzone: f32,
+    /// Multiplies the raw `x` and `y` readings, to calibrate pads that don't
+    /// read a full `-1` to `1`.
+    pub scale: [f32; 2],
+    /// How far the thumb must move along `y` within `swipe_window` seconds
+    /// for `swipe_y` to fire.
+    pub swipe_threshold: f32,
+    pub swipe_window: f32,
+    pos: Option<[f32; 2]>,
+    delta: [f32; 2],
+    time: f32,
+    /// Recent `y` movements and when they happened.
+    moves: VecDeque<(f32, f32)>,
+    /// Set once a swipe fires, until the movement dies down, so that one
+    /// swipe fires once.
+    swiped: bool,
+    swipe: Option<SwipeDirection>,
+}
+
+impl PadFilter {
+    pub fn new() -> PadFilter {
+        PadFilter {
+            deadzone: DEADZONE,
+            scale: [1., 1.],
+            swipe_threshold: SWIPE_THRESHOLD,
+            swipe_window: SWIPE_WINDOW,
+            pos: None,
+            delta: [0., 0.],
+            time: 0.,
+            moves: VecDeque::new(),
+            swiped: false,
+            swipe: None,
+        }
+    }
+
+    /// Take the latest pad reading, `dt` seconds after the last one. A
+    /// reading of exactly zero means the thumb is off the pad.
+    pub fn update(&mut self, pad: [f32; 2], dt: f32) {
+        self.delta = [0., 0.];
+        self.swipe = None;
+        if pad[0] == 0. && pad[1] == 0. {
+            self.reset();
+            return;
+        }
+        let raw = [pad[0] * self.scale[0], pad[1] * self.scale[1]];
+
+        // The first touch is a jump from nowhere, not a movement
+        let pos = match self.pos {
+            Some(pos) => pos,
+            None => {
+                self.pos = Some(raw);
+                return;
+            },
+        };
+        let off = [raw[0] - pos[0], raw[1] - pos[1]];
+        let len = (off[0] * off[0] + off[1] * off[1]).sqrt();
+        if len > self.deadzone {
+            let k = 1. - self.deadzone / len;
+            self.delta = [off[0] * k, off[1] * k];
+            self.pos = Some([pos[0] + self.delta[0], pos[1] + self.delta[1]]);
+        }
+
+        // Sum up the recent movement to find swipes
+        self.time += dt;
+        self.moves.push_back((self.time, self.delta[1]));
+        while self.moves.front().map_or(false, |&(t, _)| self.time - t > self.swipe_window) {
+            self.moves.pop_front();
+        }
+        let sum: f32 = self.moves.iter().map(|&(_, d)| d).sum();
+        if self.swiped {
+            self.swiped = sum.abs() >= self.swipe_threshold / 2.;
+        } else if sum.abs() >= self.swipe_threshold {
+            self.swiped = true;
+            self.swipe = Some(if sum > 0. { SwipeDirection::Up } else { SwipeDirection::Down });
+        }
+    }
+
+    /// Forget everything, as when the thumb leaves the pad.
+    pub fn reset(&mut self) {
+        self.pos = None;
+        self.delta = [0., 0.];
+        self.time = 0.;
+        self.moves.clear();
+        self.swiped = false;
+        self.swipe = None;
+    }
+
+    /// Check if the thumb is on the pad.
+    pub fn touched(&self) -> bool {
+        self.pos.is_some()
+    }
+
+    /// Get how far the thumb moved since the last update, ignoring noise.
+    pub fn delta(&self) -> [f32; 2] {
+        self.delta
+    }
+
+    /// Get the direction of a swipe along `y` that completed in the last
+    /// update. Each swipe is reported once.
+    pub fn swipe_y(&self) -> Option<SwipeDirection> {
+        self.swipe
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// Feed `y` readings to a fresh filter at 90 fps, and get every swipe.
+    fn swipes(ys: &[f32]) -> Vec<SwipeDirection> {
+        let mut pad = PadFilter::new();
+        ys.iter()
+            .filter_map(|&y| {
+                pad.update([0.1, y], 1. / 90.);
+                pad.swipe_y()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn resting_thumb_noise_fires_no_swipe() {
+        let ys: Vec<f32> = (0..900)
+            .map(|i| 0.2 + 0.02 * ((i * 7919 % 13) as f32 / 6. - 1.))
+            .collect();
+        assert_eq!(swipes(&ys), vec![]);
+    }
+
+    #[test]
+    fn slow_drift_fires_no_swipe() {
+        // A full pad length, but over two seconds
+        let ys: Vec<f32> = (0..180).map(|i| -0.5 + i as f32 / 180.).collect();
+        assert_eq!(swipes(&ys), vec![]);
+    }
+
+    #[test]
+    fn clean_swipe_fires_once() {
+        let mut ys = vec![-0.6; 10];
+        ys.extend((0..10).map(|i| -0.6 + 0.12 * i as f32));
+        ys.extend(vec![0.6; 30]);
+        assert_eq!(swipes(&ys), vec![SwipeDirection::Up]);
+
+        let ys: Vec<f32> = ys.iter().map(|y| -y).collect();
+        assert_eq!(swipes(&ys), vec![SwipeDirection::Down]);
+    }
+
+    #[test]
+    fn lifting_the_thumb_forgets_everything() {
+        let mut pad = PadFilter::new();
+        for i in 0..10 {
+            pad.update([0.1, 0.05 * i as f32], 1. / 90.);
+        }
+        pad.update([0., 0.], 1. / 90.);
+        assert!(!pad.touched());
+        assert_eq!(pad.time, 0.);
+        assert!(pad.moves.is_empty());
+
+        // Half a swipe before lifting plus half after isn't a swipe
+        let mut fired = false;
+        for i in 0..10 {
+            pad.update([0.1, 0.45 + 0.02 * i as f32], 1. / 90.);
+            fired |= pad.swipe_y().is_some();
+        }
+        assert!(!fired);
+    }
+}
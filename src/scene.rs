@@ -0,0 +1,249 @@
+//! What the workbench starts out with, so that the scene can be changed
+//! without recompiling. Descriptions are checked and turned into a `Scene`
+//! here, without touching the GPU, and `App` uploads whatever it needs.
+
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+
+use app::{MaterialId, DARK_PLASTIC};
+#[cfg(feature = "scene")]
+use error::{LoadWarning, WorkbenchError};
+#[cfg(feature = "scene")]
+use serde::Deserialize;
+#[cfg(feature = "scene")]
+use std::path::Path;
+
+const PI: f32 = ::std::f32::consts::PI;
+
+/// A cube the model starts with, and respawns when it leaves the kill
+/// volume.
+#[derive(Clone, Debug)]
+pub struct CubeSpawn {
+    pub pose: Isometry3<f32>,
+    /// Half the length of an edge.
+    pub radius: f32,
+    pub mat: MaterialId,
+    /// Cubes that can't be grabbed still stop lasers and fall.
+    pub grabbable: bool,
+}
+
+/// A flat square lying in the `xz` plane of its pose. Cubes land on its top
+/// side as they do on the floor, so it should be level.
+#[derive(Clone, Debug)]
+pub struct PlaneSpawn {
+    pub pose: Isometry3<f32>,
+    /// Half the length of an edge.
+    pub radius: f32,
+    pub mat: MaterialId,
+}
+
+/// Everything the workbench starts with, besides the floor and controllers.
+#[derive(Clone, Debug)]
+pub struct Scene {
+    pub cubes: Vec<CubeSpawn>,
+    pub planes: Vec<PlaneSpawn>,
+}
+
+impl Scene {
+    /// Ten cubes in a circle, each a little smaller than the last.
+    pub fn circle() -> Scene {
+        Scene {
+            cubes: (0i32..10).map(|i| {
+                let theta = (i as f32) / 5. * PI;
+                CubeSpawn {
+                    pose: Isometry3::from_parts(
+                        Translation3::new(theta.sin() * 1., 0., theta.cos() * 1.),
+                        UnitQuaternion::from_axis_angle(&Vector3::y_axis(), theta)
+                    ),
+                    radius: 0.2 * (1. - i as f32 / 15.),
+                    mat: DARK_PLASTIC,
+                    grabbable: true,
+                }
+            }).collect(),
+            planes: Vec::new(),
+        }
+    }
+
+    /// Load a scene from a RON file.
+    #[cfg(feature = "scene")]
+    pub fn load<P: AsRef<Path>>(path: P) -> Result<Scene, WorkbenchError> {
+        use std::fs::File;
+        use std::io::Read;
+
+        let path = path.as_ref();
+        let mut text = String::new();
+        File::open(path)
+            .and_then(|mut f| f.read_to_string(&mut text))
+            .map_err(|e| WorkbenchError::asset(path, e))?;
+        let desc: SceneDesc = ::ron::de::from_str(&text)
+            .map_err(|e| WorkbenchError::asset_parse(path, e))?;
+        Scene::from_desc(&desc).map_err(WorkbenchError::SceneValidation)
+    }
+
+    /// Check a scene description, listing everything wrong with every bad
+    /// object.
+    #[cfg(feature = "scene")]
+    pub fn from_desc(desc: &SceneDesc) -> Result<Scene, Vec<LoadWarning>> {
+        let mut scene = Scene {
+            cubes: Vec::new(),
+            planes: Vec::new(),
+        };
+        let mut warnings = Vec::new();
+        for (i, obj) in desc.objects.iter().enumerate() {
+            let before = warnings.len();
+            {
+                let mut warn = |detail: String| warnings.push(LoadWarning {
+                    subject: format!("object {} ({:?})", i, obj.shape),
+                    detail: detail,
+                });
+                if MaterialId::named(&obj.material).is_none() {
+                    warn(format!("unknown material \"{}\"", obj.material));
+                }
+                if !obj.position.iter().all(|x| x.is_finite()) {
+                    warn(format!("position {:?} is not finite", obj.position));
+                }
+                if !obj.yaw.is_finite() {
+                    warn(format!("yaw {} is not finite", obj.yaw));
+                }
+                if !(obj.size > 0.) || !obj.size.is_finite() {
+                    warn(format!("size {} is not a positive number", obj.size));
+                }
+            }
+            if warnings.len() > before { continue }
+
+            let mat = MaterialId::named(&obj.material).unwrap();
+            let pose = Isometry3::from_parts(
+                Translation3::new(obj.position[0], obj.position[1], obj.position[2]),
+                UnitQuaternion::from_axis_angle(&Vector3::y_axis(), obj.yaw.to_radians()),
+            );
+            let radius = obj.size / 2.;
+            match obj.shape {
+                ShapeDesc::Cube => scene.cubes.push(CubeSpawn {
+                    pose: pose,
+                    radius: radius,
+                    mat: mat,
+                    grabbable: obj.grabbable,
+                }),
+                ShapeDesc::Plane => scene.planes.push(PlaneSpawn {
+                    pose: pose,
+                    radius: radius,
+                    mat: mat,
+                }),
+            }
+        }
+        if warnings.is_empty() { Ok(scene) } else { Err(warnings) }
+    }
+}
+
+/// A scene as written in a file, e.g.
+///
+/// ```text
+/// (objects: [
+///     (shape: Cube, size: 0.4, position: (0.0, 0.0, 1.0), yaw: 45.0, material: "dark_plastic"),
+///     (shape: Plane, size: 1.0, position: (0.0, 0.75, -2.0), material: "floor"),
+/// ])
+/// ```
+#[cfg(feature = "scene")]
+#[derive(Clone, Debug, Deserialize)]
+pub struct SceneDesc {
+    pub objects: Vec<ObjectDesc>,
+}
+
+#[cfg(feature = "scene")]
+#[derive(Clone, Debug, Deserialize)]
+pub struct ObjectDesc {
+    pub shape: ShapeDesc,
+    /// The length of an edge, in meters.
+    pub size: f32,
+    pub position: [f32; 3],
+    /// The turn about the up axis, in degrees.
+    #[serde(default)]
+    pub yaw: f32,
+    /// One of the names accepted by `MaterialId::named`.
+    pub material: String,
+    /// Defaults to `true`. Planes can never be grabbed, so ignore it.
+    #[serde(default = "yes")]
+    pub grabbable: bool,
+}
+
+#[cfg(feature = "scene")]
+fn yes() -> bool { true }
+
+#[cfg(feature = "scene")]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Deserialize)]
+pub enum ShapeDesc {
+    Cube,
+    Plane,
+}
+
+#[cfg(all(test, feature = "scene"))]
+mod tests {
+    use super::*;
+
+    fn object(shape: ShapeDesc, material: &str) -> ObjectDesc {
+        ObjectDesc {
+            shape: shape,
+            size: 0.4,
+            position: [0., 1., -1.],
+            yaw: 0.,
+            material: material.into(),
+            grabbable: true,
+        }
+    }
+
+    fn details(desc: &SceneDesc) -> Vec<String> {
+        Scene::from_desc(desc).unwrap_err().iter().map(|w| w.to_string()).collect()
+    }
+
+    #[test]
+    fn bundled_scene_is_valid() {
+        let desc: SceneDesc = ::ron::de::from_str(include_str!("../assets/scene.ron")).unwrap();
+        let scene = Scene::from_desc(&desc).unwrap();
+        assert_eq!((scene.cubes.len(), scene.planes.len()), (3, 1));
+        assert!(scene.cubes.iter().all(|c| c.radius > 0.));
+    }
+
+    #[test]
+    fn unknown_materials_are_named() {
+        let desc = SceneDesc { objects: vec![
+            object(ShapeDesc::Cube, "dark_plastic"),
+            object(ShapeDesc::Plane, "wood"),
+        ]};
+        assert_eq!(details(&desc), vec!["object 1 (Plane): unknown material \"wood\""]);
+    }
+
+    #[test]
+    fn nan_positions_are_caught() {
+        let mut obj = object(ShapeDesc::Cube, "plastic");
+        obj.position[1] = ::std::f32::NAN;
+        let desc = SceneDesc { objects: vec![obj] };
+        assert_eq!(details(&desc), vec!["object 0 (Cube): position [0.0, NaN, -1.0] is not finite"]);
+    }
+
+    #[test]
+    fn bad_sizes_are_caught() {
+        let mut objects = Vec::new();
+        for &size in &[0., -1., ::std::f32::INFINITY] {
+            let mut obj = object(ShapeDesc::Cube, "plastic");
+            obj.size = size;
+            objects.push(obj);
+        }
+        assert_eq!(details(&SceneDesc { objects: objects }), vec![
+            "object 0 (Cube): size 0 is not a positive number",
+            "object 1 (Cube): size -1 is not a positive number",
+            "object 2 (Cube): size inf is not a positive number",
+        ]);
+    }
+
+    #[test]
+    fn every_problem_is_listed() {
+        let mut bad = object(ShapeDesc::Cube, "gold");
+        bad.yaw = ::std::f32::NAN;
+        bad.size = 0.;
+        let desc = SceneDesc { objects: vec![object(ShapeDesc::Cube, "plastic"), bad] };
+        assert_eq!(details(&desc), vec![
+            "object 1 (Cube): unknown material \"gold\"",
+            "object 1 (Cube): yaw NaN is not finite",
+            "object 1 (Cube): size 0 is not a positive number",
+        ]);
+    }
+}
@@ -0,0 +1,144 @@
+//! Stand-ins for the VR controllers, driven by a mouse and keyboard, so that
+//! interactions can be tried out without a headset.
+
+use nalgebra::{Isometry3, Matrix4, Perspective3, Point3, Translation3, UnitQuaternion, Vector3};
+use flight::vr::{Trackable, ViveController};
+
+/// How far in front of the camera simulated controllers are held.
+const HOLD_DISTANCE: f32 = 0.3;
+/// How far along the pad one notch of the mouse wheel moves the thumb.
+const WHEEL_STEP: f32 = 0.1;
+/// How long (in seconds) the thumb stays on the pad after the wheel stops.
+const PAD_RELEASE: f32 = 0.25;
+
+/// A camera looking at the scene from a desk, as if standing just behind
+/// the middle of the play area.
+pub fn desk_camera() -> Isometry3<f32> {
+    Isometry3::look_at_rh(
+        &Point3::new(0., 1.6, 2.),
+        &Point3::new(0., 0.6, 0.),
+        &Vector3::y(),
+    ).inverse()
+}
+
+/// The matrix that takes the scene onto the screen, as seen from `camera`.
+/// `fov` is the vertical field of view in radians, and `near` and `far` are
+/// the clipping planes.
+pub fn desk_projection(camera: &Isometry3<f32>, fov: f32, aspect: f32, near: f32, far: f32) -> Matrix4<f32> {
+    Perspective3::new(aspect, fov, near, far).to_homogeneous()
+        * camera.inverse().to_homogeneous()
+}
+
+/// A controller held in front of a camera, pointing wherever the mouse is.
+#[derive(Clone, Debug)]
+pub struct SimController {
+    pub pose: Isometry3<f32>,
+    /// From `0` (released) to `1` (fully pulled).
+    pub trigger: f32,
+    /// Where the thumb is on the pad, or zero if it's off.
+    pub pad: [f32; 2],
+    /// Seconds since the wheel last moved the thumb.
+    idle: f32,
+}
+
+impl SimController {
+    /// Start out held at `pose`, before the mouse first moves.
+    pub fn at(pose: Isometry3<f32>) -> SimController {
+        SimController {
+            pose: pose,
+            trigger: 0.,
+            pad: [0., 0.],
+            idle: 0.,
+        }
+    }
+
+    /// Point along the ray from `camera` through a point on screen. `x` and
+    /// `y` run from `-1` to `1` across the screen, with `y` up, and `fov` is
+    /// the vertical field of view in radians.
+    pub fn aim(&mut self, camera: &Isometry3<f32>, fov: f32, aspect: f32, x: f32, y: f32) {
+        let half = (fov / 2.).tan();
+        let dir = camera.rotation * Vector3::new(x * half * aspect, y * half, -1.).normalize();
+        let rot = UnitQuaternion::rotation_between(&-Vector3::z(), &dir)
+            .unwrap_or(camera.rotation);
+        let origin = camera * Point3::origin() + dir * HOLD_DISTANCE;
+        self.pose = Isometry3::from_parts(Translation3::from_vector(origin.coords), rot);
+    }
+
+    /// Pull or release the trigger all the way.
+    pub fn press(&mut self, pressed: bool) {
+        self.trigger = if pressed { 1. } else { 0. };
+    }
+
+    /// Slide the thumb along the pad by a number of wheel notches, putting it
+    /// on the pad if it isn't already.
+    pub fn scroll(&mut self, notches: f32) {
+        let y = (self.pad[1] + notches * WHEEL_STEP).max(-1.).min(1.);
+        // Zero means no thumb, so nudge away from it
+        self.pad = [0., if y == 0. { 1e-3 } else { y }];
+        self.idle = 0.;
+    }
+
+    /// Let time pass, taking the thumb off the pad once the wheel stops.
+    pub fn update(&mut self, dt: f32) {
+        self.idle += dt;
+        if self.idle > PAD_RELEASE {
+            self.pad = [0., 0.];
+        }
+    }
+
+    /// Copy this controller's state into `c`, as if it had been tracked.
+    pub fn apply_to(&self, c: &mut ViveController) {
+        c.trigger_delta = self.trigger - c.trigger;
+        c.trigger = self.trigger;
+        for i in 0..2 {
+            c.pad_delta[i] = self.pad[i] - c.pad[i];
+            c.pad[i] = self.pad[i];
+        }
+        c.pose = self.pose;
+    }
+}
+
+impl Trackable for SimController {
+    fn pose(&self) -> Isometry3<f32> {
+        self.pose
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FOV: f32 = 1.2;
+    const ASPECT: f32 = 1.5;
+
+    /// Where a point lands on screen, from `-1` to `1` on both axes.
+    fn on_screen(proj: &Matrix4<f32>, p: Point3<f32>) -> (f32, f32) {
+        let h = proj * p.to_homogeneous();
+        (h.x / h.w, h.y / h.w)
+    }
+
+    #[test]
+    fn aimed_lasers_land_under_the_mouse() {
+        let camera = desk_camera();
+        let proj = desk_projection(&camera, FOV, ASPECT, 0.1, 75.);
+        let mut sim = SimController::at(camera);
+        for &(x, y) in &[(0., 0.), (0.5, -0.25), (-0.9, 0.8)] {
+            sim.aim(&camera, FOV, ASPECT, x, y);
+            // Anywhere along the laser should be right under the mouse
+            for &along in &[0., 1., 10.] {
+                let p = sim.pose * Point3::new(0., 0., -along);
+                let (sx, sy) = on_screen(&proj, p);
+                assert!((sx - x).abs() < 1e-4 && (sy - y).abs() < 1e-4,
+                    "({}, {}) landed at ({}, {})", x, y, sx, sy);
+            }
+        }
+    }
+
+    #[test]
+    fn desk_camera_looks_at_the_middle_of_the_screen() {
+        let camera = desk_camera();
+        let proj = desk_projection(&camera, FOV, ASPECT, 0.1, 75.);
+        let (x, y) = on_screen(&proj, Point3::new(0., 0.6, 0.));
+        assert!(x.abs() < 1e-5 && y.abs() < 1e-5, "({}, {})", x, y);
+    }
+}
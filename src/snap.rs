@@ -0,0 +1,108 @@
+use nalgebra::{Isometry3, Translation3, UnitQuaternion, Vector3};
+
+const PI2: f32 = 2. * ::std::f32::consts::PI;
+
+/// A grid that poses can be snapped to, e.g. to line objects up neatly.
+#[derive(Clone, Copy, Debug)]
+pub struct SnapGrid {
+    /// The pose of the grid in the world. Its `y` axis is the grid's up.
+    pub origin: Isometry3<f32>,
+    /// The distance between neighbouring grid points.
+    pub spacing: f32,
+    /// How many snapped turns make up a full turn about the up axis, or `0`
+    /// to leave rotations alone.
+    pub angular_steps: u32,
+}
+
+impl SnapGrid {
+    pub fn new(origin: Isometry3<f32>, spacing: f32, angular_steps: u32) -> SnapGrid {
+        SnapGrid {
+            origin: origin,
+            spacing: spacing,
+            angular_steps: angular_steps,
+        }
+    }
+
+    /// Move a pose to the nearest grid point, turned about the grid's up axis
+    /// to the nearest angular step. Any tilt is removed when snapping turns,
+    /// so the result is upright. Poses exactly halfway between two grid
+    /// points snap to the one farther from the origin.
+    pub fn snap_isometry(&self, pose: &Isometry3<f32>) -> Isometry3<f32> {
+        let local = self.origin.inverse() * pose;
+
+        let mut pos = local.translation.vector;
+        if self.spacing > 0. {
+            pos = pos.map(|x| (x / self.spacing).round() * self.spacing);
+        }
+
+        let rot = if self.angular_steps > 0 {
+            let step = PI2 / self.angular_steps as f32;
+            let turns = (twist(&local.rotation) / step).round();
+            UnitQuaternion::from_axis_angle(&Vector3::y_axis(), turns * step)
+        } else {
+            local.rotation
+        };
+
+        self.origin * Isometry3::from_parts(Translation3::from_vector(pos), rot)
+    }
+}
+
+/// Find how far `rot` turns about the `y` axis, in `[-π, π]`, ignoring any
+/// tilt away from it.
+fn twist(rot: &UnitQuaternion<f32>) -> f32 {
+    // The quaternion's `y` coordinate is its `j` part
+    let q = rot.unwrap().coords;
+    // `q` and `-q` are the same rotation, so pick the one that turns less
+    let (w, y) = if q.w < 0. { (-q.w, -q.y) } else { (q.w, q.y) };
+    if w.abs() < 1e-6 && y.abs() < 1e-6 {
+        // Flipped upside down, so any turn is as good as another
+        0.
+    } else {
+        2. * y.atan2(w)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use nalgebra as na;
+
+    fn turned(degrees: f32) -> Isometry3<f32> {
+        Isometry3::new(na::zero(), Vector3::y() * degrees.to_radians())
+    }
+
+    fn turn_of(pose: &Isometry3<f32>) -> f32 {
+        twist(&pose.rotation).to_degrees()
+    }
+
+    #[test]
+    fn turns_snap_across_the_wrap() {
+        let grid = SnapGrid::new(na::one(), 0., 4);
+        assert!(turn_of(&grid.snap_isometry(&turned(350.))).abs() < 1e-3);
+        assert!(turn_of(&grid.snap_isometry(&turned(10.))).abs() < 1e-3);
+        // Either side of a half turn is the same half turn
+        assert!((turn_of(&grid.snap_isometry(&turned(170.))).abs() - 180.).abs() < 1e-3);
+        assert!((turn_of(&grid.snap_isometry(&turned(190.))).abs() - 180.).abs() < 1e-3);
+        assert!((turn_of(&grid.snap_isometry(&turned(-100.))) + 90.).abs() < 1e-3);
+
+        // Tilted poses come out upright
+        let tilted = Isometry3::new(na::zero(), Vector3::new(0.3, 80f32.to_radians(), 0.));
+        let up = grid.snap_isometry(&tilted).rotation * Vector3::y();
+        assert!((up - Vector3::y()).norm() < 1e-5);
+    }
+
+    #[test]
+    fn halfway_snaps_away_from_the_origin() {
+        let grid = SnapGrid::new(na::one(), 0.5, 0);
+        let at = |x: f32, y: f32, z: f32| grid
+            .snap_isometry(&Isometry3::new(Vector3::new(x, y, z), na::zero()))
+            .translation.vector;
+        assert_eq!(at(0.25, -0.25, 0.74), Vector3::new(0.5, -0.5, 0.5));
+        assert_eq!(at(0.24, -0.24, 0.76), Vector3::new(0., 0., 1.));
+
+        // Relative to the grid's own origin
+        let grid = SnapGrid::new(Isometry3::new(Vector3::new(0.1, 0., 0.), na::zero()), 0.5, 0);
+        let snapped = grid.snap_isometry(&Isometry3::new(Vector3::new(0.4, 0., 0.), na::zero()));
+        assert!((snapped.translation.vector.x - 0.6).abs() < 1e-5);
+    }
+}
@@ -0,0 +1,128 @@
+use std::time::Instant;
+
+use error::BudgetOverrun;
+
+/// How much of each new frame delta is mixed into `FrameTiming::smoothed`.
+const SMOOTHING: f32 = 0.1;
+/// The time each frame is given by default, in seconds: one refresh of a
+/// 90Hz headset.
+pub const FRAME_BUDGET: f32 = 1. / 90.;
+/// How many budgets a frame may take before it counts as over, since frame
+/// times jitter a little around the refresh interval.
+const BUDGET_SLACK: f32 = 1.5;
+/// Frames taking at least this many budgets are stalls, rather than a single
+/// missed refresh.
+const STALL_BUDGETS: f32 = 4.;
+
+/// Keeps track of time from frame to frame, so that animation and physics
+/// run at the same speed regardless of the frame rate.
+pub struct FrameTiming {
+    last: Option<Instant>,
+    /// Seconds since the previous frame, or `0` on the first frame.
+    pub delta: f32,
+    /// `delta` averaged over recent frames, for things that shouldn't jitter
+    /// along with the frame rate.
+    pub smoothed: f32,
+    /// Seconds since the first frame.
+    pub elapsed: f32,
+    /// The number of frames so far, including the current one.
+    pub frame: u64,
+    /// The seconds each frame is allowed to take.
+    pub budget: f32,
+}
+
+impl FrameTiming {
+    pub fn new() -> FrameTiming {
+        FrameTiming {
+            last: None,
+            delta: 0.,
+            smoothed: 0.,
+            elapsed: 0.,
+            frame: 0,
+            budget: FRAME_BUDGET,
+        }
+    }
+
+    /// Start a new frame now.
+    pub fn tick(&mut self) {
+        self.tick_at(Instant::now());
+    }
+
+    /// Start a new frame at the given instant.
+    pub fn tick_at(&mut self, now: Instant) {
+        let dt = match self.last {
+            Some(last) if now > last => {
+                let d = now.duration_since(last);
+                d.as_secs() as f32 + d.subsec_nanos() as f32 * 1e-9
+            },
+            _ => 0.,
+        };
+        self.last = Some(now);
+        self.advance(dt);
+    }
+
+    /// Start a new frame `dt` seconds after the previous one, without looking
+    /// at the clock. Useful to run frames at a fixed rate.
+    pub fn advance(&mut self, dt: f32) {
+        self.delta = dt;
+        self.smoothed = if self.frame == 0 {
+            dt
+        } else {
+            self.smoothed + (dt - self.smoothed) * SMOOTHING
+        };
+        self.elapsed += dt;
+        self.frame += 1;
+    }
+
+    /// Check if the previous frame ran over its budget, and if so by how
+    /// much. Frames that missed one refresh are classed `"missed"`, and
+    /// longer ones `"stall"`.
+    pub fn overrun(&self) -> Option<BudgetOverrun> {
+        if self.frame < 2 || self.delta <= self.budget * BUDGET_SLACK {
+            return None;
+        }
+        Some(BudgetOverrun {
+            class: if self.delta >= self.budget * STALL_BUDGETS { "stall" } else { "missed" },
+            spent: self.delta,
+            budget: self.budget,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn frames_within_budget_are_fine() {
+        let mut timing = FrameTiming::new();
+        timing.advance(0.);
+        assert!(timing.overrun().is_none());
+        for &dt in &[FRAME_BUDGET, FRAME_BUDGET * 1.2, FRAME_BUDGET * 0.8] {
+            timing.advance(dt);
+            assert!(timing.overrun().is_none());
+        }
+    }
+
+    #[test]
+    fn slow_frames_are_classed_by_length() {
+        let mut timing = FrameTiming::new();
+        timing.advance(0.);
+        timing.advance(FRAME_BUDGET * 2.);
+        let missed = timing.overrun().unwrap();
+        assert_eq!(missed.class, "missed");
+        assert_eq!(missed.spent, FRAME_BUDGET * 2.);
+        assert_eq!(missed.budget, FRAME_BUDGET);
+        timing.advance(0.5);
+        assert_eq!(timing.overrun().unwrap().class, "stall");
+    }
+
+    #[test]
+    fn first_frame_is_never_over() {
+        // The first frame has nothing to measure against, however long
+        // loading took
+        let mut timing = FrameTiming::new();
+        timing.advance(3.);
+        assert!(timing.overrun().is_none());
+    }
+}